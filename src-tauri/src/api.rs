@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Registry entry for a Tauri command exposed to the frontend
+#[derive(Debug, Clone, Copy)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub replaced_by: Option<&'static str>,
+}
+
+impl CommandSpec {
+    const fn current(name: &'static str) -> Self {
+        Self {
+            name,
+            replaced_by: None,
+        }
+    }
+
+    const fn deprecated(name: &'static str, replaced_by: &'static str) -> Self {
+        Self {
+            name,
+            replaced_by: Some(replaced_by),
+        }
+    }
+}
+
+/// Every command registered with the invoke handler. Keep in sync with
+/// `register_commands!` in `lib.rs` when adding or retiring commands.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec::current("greet"),
+    CommandSpec::current("create_knowledge_node"),
+    CommandSpec::current("update_node"),
+    CommandSpec::current("process_query"),
+    CommandSpec::current("semantic_search"),
+    CommandSpec::current("get_nodes_for_date"),
+    CommandSpec::current("update_node_content"),
+    CommandSpec::current("update_node_structure"),
+    CommandSpec::current("delete_node"),
+    CommandSpec::deprecated("create_node_for_date", "upsert_node"),
+    CommandSpec::deprecated("create_node_for_date_with_id", "upsert_node"),
+    CommandSpec::current("get_today_date"),
+    CommandSpec::current("upsert_node"),
+    CommandSpec::current("create_image_node"),
+    CommandSpec::current("process_dropped_files"),
+    CommandSpec::current("multimodal_search"),
+    CommandSpec::current("rename_image_file"),
+    CommandSpec::current("get_nodes_by_time_of_day"),
+    CommandSpec::current("validate_node_metadata"),
+    CommandSpec::current("autolink_mentions"),
+    CommandSpec::current("get_date_bounds"),
+    CommandSpec::current("retry_failed_embeddings"),
+    CommandSpec::current("render_date_html"),
+    CommandSpec::current("replace_in_subtree"),
+    CommandSpec::current("detect_sibling_conflicts"),
+    CommandSpec::current("backfill_thumbnails"),
+    CommandSpec::current("cluster_nodes"),
+    CommandSpec::deprecated("set_default_model", "set_active_model"),
+    CommandSpec::current("get_model_limits"),
+    CommandSpec::current("set_node_collapsed"),
+    CommandSpec::current("tag_search_results"),
+    CommandSpec::current("verify_node_dates"),
+    CommandSpec::current("generate_text"),
+    CommandSpec::current("get_acceleration_info"),
+    CommandSpec::current("create_children"),
+    CommandSpec::current("get_subtree_text"),
+    CommandSpec::current("find_empty_nodes"),
+    CommandSpec::current("get_database_version"),
+    CommandSpec::current("migrate_database"),
+    CommandSpec::current("rank_nodes_by_text"),
+    CommandSpec::current("find_oversized_metadata"),
+    CommandSpec::current("export_date_conversations"),
+    CommandSpec::current("get_tag_counts_for_range"),
+    CommandSpec::current("create_node_with_embedding"),
+    CommandSpec::current("get_api_info"),
+    CommandSpec::current("get_stale_nodes"),
+    CommandSpec::current("set_node_reminder"),
+    CommandSpec::current("get_due_reminders"),
+    CommandSpec::current("dismiss_reminder"),
+    CommandSpec::current("get_node_depth"),
+    CommandSpec::current("find_encoding_issues"),
+    CommandSpec::current("process_query_stream"),
+    CommandSpec::current("create_nodes_for_date_batch"),
+    CommandSpec::current("get_graph_snapshot"),
+    CommandSpec::current("resnippet"),
+    CommandSpec::current("verify_command_registry"),
+    CommandSpec::current("get_subtree_centroid"),
+    CommandSpec::current("get_representative_node"),
+    CommandSpec::current("stream_export_range"),
+    CommandSpec::current("set_node_sort_key"),
+    CommandSpec::current("get_children_sorted_by_key"),
+    CommandSpec::current("delete_nodes_batch"),
+    CommandSpec::current("get_latency_percentiles"),
+    CommandSpec::current("get_service_status"),
+    CommandSpec::current("reconcile_node"),
+    CommandSpec::current("export_subtree_bundle"),
+    CommandSpec::current("import_subtree_bundle"),
+    CommandSpec::current("move_node"),
+    CommandSpec::current("export_date_as_markdown"),
+    CommandSpec::current("import_markdown"),
+    CommandSpec::current("duplicate_node"),
+    CommandSpec::current("get_node_ancestors"),
+    CommandSpec::current("get_node_children"),
+    CommandSpec::current("reconfigure_service"),
+    CommandSpec::current("get_full_image"),
+    CommandSpec::current("update_node_metadata"),
+    CommandSpec::current("toggle_task_completion"),
+    CommandSpec::current("text_search"),
+    CommandSpec::current("hybrid_search"),
+    CommandSpec::current("cancel_operation"),
+    CommandSpec::current("get_recent_nodes"),
+    CommandSpec::current("count_nodes_for_date"),
+    CommandSpec::current("count_nodes_for_range"),
+    CommandSpec::current("regenerate_ai_chat"),
+    CommandSpec::current("find_similar_nodes"),
+    CommandSpec::current("list_available_models"),
+    CommandSpec::current("set_active_model"),
+    CommandSpec::current("get_log_file_path"),
+    CommandSpec::current("open_logs_directory"),
+    CommandSpec::current("get_nodes_for_date_since"),
+    CommandSpec::current("search_in_subtree"),
+    CommandSpec::current("get_embedding_dimension"),
+    CommandSpec::current("create_task_node"),
+    CommandSpec::current("get_tasks"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandInfo {
+    pub name: String,
+    pub deprecated: bool,
+    pub replaced_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiInfo {
+    pub version: String,
+    pub commands: Vec<CommandInfo>,
+}
+
+pub fn api_info() -> ApiInfo {
+    ApiInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commands: COMMANDS
+            .iter()
+            .map(|spec| CommandInfo {
+                name: spec.name.to_string(),
+                deprecated: spec.replaced_by.is_some(),
+                replaced_by: spec.replaced_by.map(|r| r.to_string()),
+            })
+            .collect(),
+    }
+}
+
+/// Differences between the declared command list and the names actually registered
+pub fn registry_mismatches(registered: &[&str]) -> Vec<String> {
+    let declared: HashSet<&str> = COMMANDS.iter().map(|spec| spec.name).collect();
+    let registered_set: HashSet<&str> = registered.iter().copied().collect();
+
+    let mut mismatches: Vec<String> = COMMANDS
+        .iter()
+        .filter(|spec| !registered_set.contains(spec.name))
+        .map(|spec| format!("{} is declared but not registered", spec.name))
+        .collect();
+    mismatches.extend(
+        registered
+            .iter()
+            .filter(|name| !declared.contains(*name))
+            .map(|name| format!("{} is registered but not declared", name)),
+    );
+    mismatches
+}
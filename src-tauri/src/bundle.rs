@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use nodespace_core_types::{Node, NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::hierarchy::{build_outline, OutlineNode};
+use crate::render::is_image_node;
+
+/// Node list written at the top of a bundle directory
+pub const MANIFEST_FILE: &str = "nodes.json";
+
+/// Directory, relative to the bundle root, holding copied image files
+pub const ASSETS_DIR: &str = "assets";
+
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleNode {
+    pub node: Node,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Contents of `nodes.json`. Image nodes' `file_path` metadata is relative to the bundle
+/// root so the bundle can be moved between machines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub root_node_id: String,
+    pub nodes: Vec<BundleNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSummary {
+    pub bundle_path: String,
+    pub node_count: usize,
+    pub asset_count: usize,
+    pub includes_embeddings: bool,
+    /// Image nodes whose source file was missing, exported without their asset
+    pub missing_assets: Vec<String>,
+}
+
+/// Write a subtree to `dest` as a bundle directory: `nodes.json` plus image files copied
+/// into `assets/`. `dest` must not exist yet or be an empty directory.
+pub fn write_bundle(
+    dest: &Path,
+    root_node_id: &str,
+    nodes: Vec<BundleNode>,
+) -> AppResult<BundleSummary> {
+    let occupied = match std::fs::read_dir(dest) {
+        Ok(mut entries) => entries.next().is_some(),
+        Err(_) => dest.exists(),
+    };
+    if occupied {
+        return Err(AppError::InvalidInput(format!(
+            "Bundle destination {} must be a new or empty directory",
+            dest.display()
+        )));
+    }
+    let assets_dir = dest.join(ASSETS_DIR);
+    std::fs::create_dir_all(&assets_dir)
+        .map_err(|e| AppError::Internal(format!("Failed to create bundle directory: {}", e)))?;
+
+    let mut summary = BundleSummary {
+        bundle_path: dest.to_string_lossy().into_owned(),
+        node_count: nodes.len(),
+        asset_count: 0,
+        includes_embeddings: nodes.iter().any(|n| n.embedding.is_some()),
+        missing_assets: Vec::new(),
+    };
+
+    let mut bundled = Vec::with_capacity(nodes.len());
+    for mut entry in nodes {
+        if let Some(source) = image_source_path(&entry.node) {
+            if source.is_file() {
+                let asset_name = asset_file_name(&entry.node, &source);
+                std::fs::copy(&source, assets_dir.join(&asset_name)).map_err(|e| {
+                    AppError::Internal(format!("Failed to copy {}: {}", source.display(), e))
+                })?;
+                set_file_path(&mut entry.node, &format!("{}/{}", ASSETS_DIR, asset_name));
+                summary.asset_count += 1;
+            } else {
+                summary.missing_assets.push(entry.node.id.0.clone());
+            }
+        }
+        bundled.push(entry);
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        root_node_id: root_node_id.to_string(),
+        nodes: bundled,
+    };
+    std::fs::write(
+        dest.join(MANIFEST_FILE),
+        serde_json::to_vec_pretty(&manifest)?,
+    )
+    .map_err(|e| AppError::Internal(format!("Failed to write bundle manifest: {}", e)))?;
+
+    Ok(summary)
+}
+
+/// One node to recreate on import, already remapped to a fresh id and its new position
+#[derive(Debug, Clone)]
+pub struct PlannedImport {
+    pub original_id: String,
+    pub node_id: NodeId,
+    pub parent_id: Option<NodeId>,
+    pub before_sibling: Option<NodeId>,
+    pub content: String,
+    pub node_type: String,
+    pub metadata: Option<serde_json::Value>,
+    pub embedding: Option<Vec<f32>>,
+    /// Image file to copy from the bundle into the vault, as (source, destination)
+    pub asset: Option<(PathBuf, PathBuf)>,
+}
+
+pub fn read_bundle(bundle_root: &Path) -> AppResult<BundleManifest> {
+    let manifest_path = bundle_root.join(MANIFEST_FILE);
+    let contents = std::fs::read(&manifest_path).map_err(|e| {
+        AppError::InvalidInput(format!(
+            "Failed to read bundle manifest {}: {}",
+            manifest_path.display(),
+            e
+        ))
+    })?;
+    let manifest: BundleManifest = serde_json::from_slice(&contents)?;
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "Bundle format version {} is newer than supported version {}",
+            manifest.format_version, BUNDLE_FORMAT_VERSION
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Plan recreating a bundle's nodes under `target_parent`, parents before children and
+/// siblings in order. Every node gets a fresh id so imports never collide with existing
+/// nodes, and `references` between bundled nodes follow the new ids. Top-level bundle
+/// nodes are chained after `first_predecessor`; images are planned for copying from the
+/// bundle into `assets_dir`. A bundle whose image paths point outside the bundle is
+/// rejected.
+pub fn plan_import(
+    manifest: BundleManifest,
+    bundle_root: &Path,
+    target_parent: Option<NodeId>,
+    first_predecessor: Option<NodeId>,
+    assets_dir: &Path,
+) -> AppResult<Vec<PlannedImport>> {
+    let id_map: HashMap<String, NodeId> = manifest
+        .nodes
+        .iter()
+        .map(|entry| (entry.node.id.0.clone(), NodeId::new()))
+        .collect();
+
+    let mut embeddings = HashMap::new();
+    let mut nodes = Vec::with_capacity(manifest.nodes.len());
+    for entry in manifest.nodes {
+        if let Some(embedding) = entry.embedding {
+            embeddings.insert(entry.node.id.0.clone(), embedding);
+        }
+        nodes.push(entry.node);
+    }
+
+    let context = ImportContext {
+        id_map: &id_map,
+        bundle_root,
+        assets_dir,
+    };
+    let mut planned = Vec::with_capacity(nodes.len());
+    plan_level(
+        build_outline(nodes),
+        target_parent,
+        first_predecessor,
+        &context,
+        &mut embeddings,
+        &mut planned,
+    )?;
+    Ok(planned)
+}
+
+struct ImportContext<'a> {
+    id_map: &'a HashMap<String, NodeId>,
+    bundle_root: &'a Path,
+    assets_dir: &'a Path,
+}
+
+fn plan_level(
+    level: Vec<OutlineNode>,
+    parent: Option<NodeId>,
+    first_predecessor: Option<NodeId>,
+    context: &ImportContext<'_>,
+    embeddings: &mut HashMap<String, Vec<f32>>,
+    planned: &mut Vec<PlannedImport>,
+) -> AppResult<()> {
+    let mut previous = first_predecessor;
+    for OutlineNode { mut node, children } in level {
+        let node_id = context.id_map[&node.id.0].clone();
+
+        let asset = match image_source_path(&node) {
+            // An absolute path is the exporting machine's, or an attempt to point the node
+            // at a local file; either way there is nothing in the bundle to copy
+            Some(path) if !path.is_relative() => {
+                log::warn!(
+                    "Dropping absolute image path {} of node {} from the bundle",
+                    path.display(),
+                    node.id.0
+                );
+                clear_file_path(&mut node);
+                None
+            }
+            Some(relative) => {
+                check_inside_bundle(&node, &relative)?;
+                let source = context.bundle_root.join(relative);
+                // Only the final component of the stored name is used, so it can't steer
+                // the copy out of the assets directory
+                let filename = node
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.get("filename"))
+                    .and_then(|v| v.as_str())
+                    .and_then(|name| Path::new(name).file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| asset_file_name(&node, &source));
+                let dest = context
+                    .assets_dir
+                    .join(format!("{}-{}", node_id.0, filename));
+                Some((source, dest))
+            }
+            None => None,
+        };
+
+        if let Some((_, dest)) = asset.as_ref() {
+            set_file_path(&mut node, &dest.to_string_lossy());
+        }
+        if let Some(metadata) = node.metadata.as_mut() {
+            remap_references(metadata, context.id_map);
+        }
+
+        planned.push(PlannedImport {
+            original_id: node.id.0.clone(),
+            node_id: node_id.clone(),
+            parent_id: parent.clone(),
+            before_sibling: previous.take(),
+            content: node.content.as_str().unwrap_or_default().to_string(),
+            node_type: node.r#type,
+            metadata: node.metadata,
+            embedding: embeddings.remove(&node.id.0),
+            asset,
+        });
+
+        plan_level(
+            children,
+            Some(node_id.clone()),
+            None,
+            context,
+            embeddings,
+            planned,
+        )?;
+        previous = Some(node_id);
+    }
+    Ok(())
+}
+
+/// Reject an image path that could leave the bundle directory, such as `../../.ssh/id_rsa`
+fn check_inside_bundle(node: &Node, relative: &Path) -> AppResult<()> {
+    let escapes = relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(AppError::InvalidInput(format!(
+            "Image path {} of node {} points outside the bundle",
+            relative.display(),
+            node.id.0
+        )));
+    }
+    Ok(())
+}
+
+fn remap_references(metadata: &mut serde_json::Value, id_map: &HashMap<String, NodeId>) {
+    let Some(references) = metadata
+        .get_mut("references")
+        .and_then(|r| r.as_array_mut())
+    else {
+        return;
+    };
+    for reference in references {
+        let mapped = reference.as_str().and_then(|id| id_map.get(id));
+        if let Some(new_id) = mapped {
+            *reference = serde_json::Value::String(new_id.0.clone());
+        }
+    }
+}
+
+fn image_source_path(node: &Node) -> Option<PathBuf> {
+    if !is_image_node(node) {
+        return None;
+    }
+    node.metadata
+        .as_ref()?
+        .get("file_path")?
+        .as_str()
+        .map(PathBuf::from)
+}
+
+/// Asset names are prefixed with the node id so two images called `photo.png` can't collide
+fn asset_file_name(node: &Node, source: &Path) -> String {
+    let filename = source
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "image".to_string());
+    format!("{}-{}", node.id.0, filename)
+}
+
+fn clear_file_path(node: &mut Node) {
+    if let Some(object) = node.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+        object.remove("file_path");
+    }
+}
+
+fn set_file_path(node: &mut Node, file_path: &str) {
+    if let Some(object) = node.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+        object.insert(
+            "file_path".to_string(),
+            serde_json::Value::String(file_path.to_string()),
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+
+/// Overrides the database directory
+pub const DATABASE_PATH_ENV: &str = "NODESPACE_DATABASE_PATH";
+
+/// Overrides the directory holding the AI models
+pub const MODELS_PATH_ENV: &str = "NODESPACE_MODELS_PATH";
+
+/// Overrides the largest image file, in bytes, accepted for processing
+pub const MAX_IMAGE_BYTES_ENV: &str = "NODESPACE_MAX_IMAGE_BYTES";
+
+/// Overrides the largest serialized node metadata, in bytes, that may be stored
+pub const MAX_METADATA_BYTES_ENV: &str = "NODESPACE_MAX_METADATA_BYTES";
+
+/// Overrides the size, in bytes, at which `nodespace.log` is rotated
+pub const LOG_MAX_BYTES_ENV: &str = "NODESPACE_LOG_MAX_BYTES";
+
+/// Overrides how many rotated log files are kept
+pub const LOG_ARCHIVES_ENV: &str = "NODESPACE_LOG_ARCHIVES";
+
+/// Overrides how many times a call is retried while the services are initializing
+pub const RETRY_COUNT_ENV: &str = "NODESPACE_RETRY_COUNT";
+
+/// Overrides the delay, in milliseconds, between those retries
+pub const RETRY_DELAY_MS_ENV: &str = "NODESPACE_RETRY_DELAY_MS";
+
+/// Model the services load when no default model has been chosen
+pub const DEFAULT_MODEL: &str = "gemma-3-1b-it-onnx";
+
+/// Weights file each model directory must contain
+pub const MODEL_FILE: &str = "model.onnx";
+
+pub const DEFAULT_MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+pub const DEFAULT_MAX_METADATA_BYTES: usize = 64 * 1024;
+
+pub const DEFAULT_RETRY_COUNT: u32 = 3;
+
+pub const DEFAULT_RETRY_DELAY_MS: u64 = 2000;
+
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+pub const DEFAULT_LOG_ARCHIVES: usize = 5;
+
+/// Directory under the app data dir used when `NODESPACE_DATABASE_PATH` is not set
+pub const DATABASE_DIR_NAME: &str = "lance_db";
+
+/// Where the NodeSpace services keep their data and find their models
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub database_path: PathBuf,
+    pub models_path: PathBuf,
+    pub max_image_bytes: u64,
+    /// Cap on a node's serialized metadata, enforced on every metadata write
+    pub max_metadata_bytes: usize,
+    pub retry_count: u32,
+    pub retry_delay_ms: u64,
+    /// `nodespace.log` is rotated once it grows past this many bytes
+    pub log_max_bytes: u64,
+    /// Rotated logs kept as `nodespace.log.1` (newest) through `nodespace.log.N`
+    pub log_archives: usize,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppConfig {
+    /// Paths from `NODESPACE_DATABASE_PATH` and `NODESPACE_MODELS_PATH`. Without the first
+    /// the database path stays unset until `use_app_data_dir` fills it in; the models
+    /// fall back to the workspace `models` folder.
+    /// The image size limit comes from `NODESPACE_MAX_IMAGE_BYTES`, defaulting to 10MB, the
+    /// metadata size limit from `NODESPACE_MAX_METADATA_BYTES`, defaulting to 64KB, and
+    /// the initialization retries from `NODESPACE_RETRY_COUNT` and `NODESPACE_RETRY_DELAY_MS`,
+    /// defaulting to 3 retries 2 seconds apart. Log rotation is set by
+    /// `NODESPACE_LOG_MAX_BYTES` and `NODESPACE_LOG_ARCHIVES`, defaulting to 10MB and 5.
+    pub fn new() -> Self {
+        let mut config = Self::with_paths(
+            env_path(DATABASE_PATH_ENV).unwrap_or_default(),
+            env_path(MODELS_PATH_ENV).unwrap_or_else(default_models_path),
+        );
+        if let Some(limit) = env_number(MAX_IMAGE_BYTES_ENV) {
+            config.max_image_bytes = limit;
+        }
+        if let Some(limit) = env_number(MAX_METADATA_BYTES_ENV) {
+            config.max_metadata_bytes = limit;
+        }
+        if let Some(count) = env_number(RETRY_COUNT_ENV) {
+            config.retry_count = count;
+        }
+        if let Some(delay) = env_number(RETRY_DELAY_MS_ENV) {
+            config.retry_delay_ms = delay;
+        }
+        if let Some(limit) = env_number(LOG_MAX_BYTES_ENV) {
+            config.log_max_bytes = limit;
+        }
+        if let Some(archives) = env_number(LOG_ARCHIVES_ENV) {
+            config.log_archives = archives;
+        }
+        config
+    }
+
+    pub fn with_paths(database_path: impl Into<PathBuf>, models_path: impl Into<PathBuf>) -> Self {
+        Self {
+            database_path: database_path.into(),
+            models_path: models_path.into(),
+            max_image_bytes: DEFAULT_MAX_IMAGE_BYTES,
+            max_metadata_bytes: DEFAULT_MAX_METADATA_BYTES,
+            retry_count: DEFAULT_RETRY_COUNT,
+            retry_delay_ms: DEFAULT_RETRY_DELAY_MS,
+            log_max_bytes: DEFAULT_LOG_MAX_BYTES,
+            log_archives: DEFAULT_LOG_ARCHIVES,
+        }
+    }
+
+    /// Paths under the system temp directory, so tests never touch a real vault
+    pub fn for_testing() -> Self {
+        let root = std::env::temp_dir().join("nodespace-test");
+        Self::with_paths(root.join("lance_db"), root.join("models"))
+    }
+
+    /// Check the paths before they are handed to the services. Both must be non-empty UTF-8
+    /// paths, and neither may point at an existing regular file. Missing directories are
+    /// fine: the data store creates its own, and missing models are reported on load.
+    pub fn validate(&self) -> AppResult<()> {
+        if self.database_path.as_os_str().is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "Database path is not set. Set {} to the database directory.",
+                DATABASE_PATH_ENV
+            )));
+        }
+        check_directory_path("Database", &self.database_path)?;
+        check_directory_path("Models", &self.models_path)
+    }
+
+    /// Keep the database in `app_data_dir` unless `NODESPACE_DATABASE_PATH` chose another
+    /// directory
+    pub fn use_app_data_dir(&mut self, app_data_dir: &Path) {
+        if self.database_path.as_os_str().is_empty() {
+            self.database_path = app_data_dir.join(DATABASE_DIR_NAME);
+        }
+    }
+
+    /// Where the weights of `model` are expected
+    pub fn model_file(&self, model: &str) -> PathBuf {
+        self.models_path.join(model).join(MODEL_FILE)
+    }
+
+    /// Check that the weights of `model` are installed and readable, so a missing model is
+    /// reported when the services start rather than on the first query. A missing file is
+    /// reported as not found; one that exists but can't be read, or is empty, as failing
+    /// to load.
+    pub fn check_model(&self, model: &str) -> AppResult<()> {
+        let path = self.model_file(model);
+        let size = std::fs::File::open(&path)
+            .and_then(|file| file.metadata())
+            .map(|metadata| metadata.len())
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => AppError::ServiceInitialization(format!(
+                    "Model not found: expected {} at {}. Download the model into {}, or set {} \
+                     to the directory that contains it.",
+                    model,
+                    path.display(),
+                    path.parent().unwrap_or(&self.models_path).display(),
+                    MODELS_PATH_ENV
+                )),
+                _ => AppError::ServiceInitialization(format!(
+                    "Model failed to load: {} could not be read: {}",
+                    path.display(),
+                    e
+                )),
+            })?;
+        if size == 0 {
+            return Err(AppError::ServiceInitialization(format!(
+                "Model failed to load: {} is empty. Download the model again.",
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn database_path_str(&self) -> AppResult<&str> {
+        utf8_path("Database", &self.database_path)
+    }
+
+    pub fn models_path_str(&self) -> AppResult<&str> {
+        utf8_path("Models", &self.models_path)
+    }
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn env_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|v| v.trim().parse().ok())
+}
+
+fn default_models_path() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    manifest_dir
+        .ancestors()
+        .nth(2)
+        .unwrap_or(manifest_dir)
+        .join("models")
+}
+
+fn utf8_path<'a>(label: &str, path: &'a Path) -> AppResult<&'a str> {
+    path.to_str().ok_or_else(|| {
+        AppError::InvalidInput(format!(
+            "{} path is not valid UTF-8: {}",
+            label,
+            path.display()
+        ))
+    })
+}
+
+fn check_directory_path(label: &str, path: &Path) -> AppResult<()> {
+    if utf8_path(label, path)?.trim().is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "{} path cannot be empty",
+            label
+        )));
+    }
+    if path.is_file() {
+        return Err(AppError::InvalidInput(format!(
+            "{} path {} is a file, expected a directory",
+            label,
+            path.display()
+        )));
+    }
+    Ok(())
+}
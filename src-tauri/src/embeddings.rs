@@ -0,0 +1,121 @@
+/// Cosine similarity of two vectors; zero when either is empty or zero-length, or when
+/// the dimensions differ.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+fn squared_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// Element-wise mean of equally sized vectors. Vectors whose dimension differs from the
+/// first one are ignored.
+pub fn centroid<'a>(vectors: impl IntoIterator<Item = &'a [f32]>) -> Option<Vec<f32>> {
+    let mut iter = vectors.into_iter();
+    let first = iter.next()?;
+    let mut sum = first.to_vec();
+    let mut count = 1usize;
+
+    for vector in iter.filter(|v| v.len() == sum.len()) {
+        for (total, value) in sum.iter_mut().zip(vector) {
+            *total += value;
+        }
+        count += 1;
+    }
+
+    for total in sum.iter_mut() {
+        *total /= count as f32;
+    }
+    Some(sum)
+}
+
+/// Index of the vector closest to `target`
+pub fn nearest_index(vectors: &[Vec<f32>], target: &[f32]) -> Option<usize> {
+    vectors
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            squared_distance(a, target).total_cmp(&squared_distance(b, target))
+        })
+        .map(|(index, _)| index)
+}
+
+/// Deterministic farthest-point seeding: start from the first vector and repeatedly pick
+/// the vector farthest from every centroid chosen so far.
+pub fn seed_centroids(vectors: &[Vec<f32>], k: usize) -> Vec<Vec<f32>> {
+    let mut seeds: Vec<Vec<f32>> = Vec::with_capacity(k);
+    if let Some(first) = vectors.first() {
+        seeds.push(first.clone());
+    }
+
+    while seeds.len() < k.min(vectors.len()) {
+        let farthest = vectors
+            .iter()
+            .max_by(|a, b| {
+                let da = seeds
+                    .iter()
+                    .map(|s| squared_distance(a, s))
+                    .fold(f32::MAX, f32::min);
+                let db = seeds
+                    .iter()
+                    .map(|s| squared_distance(b, s))
+                    .fold(f32::MAX, f32::min);
+                da.total_cmp(&db)
+            })
+            .cloned();
+        match farthest {
+            Some(vector) => seeds.push(vector),
+            None => break,
+        }
+    }
+    seeds
+}
+
+/// Lloyd's k-means from the given starting centroids. Returns the cluster index assigned
+/// to each vector and the final centroids.
+pub fn kmeans(
+    vectors: &[Vec<f32>],
+    initial_centroids: Vec<Vec<f32>>,
+    max_iterations: usize,
+) -> (Vec<usize>, Vec<Vec<f32>>) {
+    let mut centroids = initial_centroids;
+    let mut assignments = vec![0usize; vectors.len()];
+
+    for iteration in 0..max_iterations.max(1) {
+        let mut changed = false;
+        for (index, vector) in vectors.iter().enumerate() {
+            let cluster = nearest_index(&centroids, vector).unwrap_or(0);
+            if cluster != assignments[index] {
+                assignments[index] = cluster;
+                changed = true;
+            }
+        }
+
+        for (cluster, centroid_vector) in centroids.iter_mut().enumerate() {
+            let members = vectors
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assigned)| **assigned == cluster)
+                .map(|(vector, _)| vector.as_slice());
+            if let Some(mean) = centroid(members) {
+                *centroid_vector = mean;
+            }
+        }
+
+        if !changed && iteration > 0 {
+            break;
+        }
+    }
+
+    (assignments, centroids)
+}
@@ -8,6 +8,9 @@ pub enum AppError {
     #[error("Service initialization failed: {0}")]
     ServiceInitialization(String),
 
+    #[error("Service initializing: {0}")]
+    ServiceInitializing(String),
+
     #[error("Data store error: {0}")]
     DataStore(String),
 
@@ -29,6 +32,12 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Operation cancelled: {0}")]
+    Cancelled(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -45,11 +54,25 @@ impl<T> From<std::sync::PoisonError<T>> for AppError {
     }
 }
 
+impl AppError {
+    /// Whether the failure is expected to clear on its own, so retrying later may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AppError::ServiceInitializing(_))
+    }
+}
+
 /// Result type alias for the application
-#[allow(dead_code)]
 pub type AppResult<T> = Result<T, AppError>;
 
-/// Convert AppError to String for Tauri command compatibility
+/// Plain-text errors carry no more specific type. Commands classify service failures with
+/// `classify_service_error` instead, so this only covers text from helpers
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+/// Convert AppError to String for legacy callers that still report errors as text
 impl From<AppError> for String {
     fn from(error: AppError) -> Self {
         error.to_string()
@@ -105,10 +128,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_serialized_error_carries_variant_tag() {
+        let cases = [
+            (
+                AppError::ServiceInitializing("loading".to_string()),
+                "ServiceInitializing",
+            ),
+            (
+                AppError::InvalidInput("loading".to_string()),
+                "InvalidInput",
+            ),
+            (AppError::NotFound("loading".to_string()), "NotFound"),
+        ];
+
+        for (error, tag) in cases {
+            assert_eq!(
+                serde_json::to_value(&error).unwrap(),
+                serde_json::json!({ "type": tag, "message": "loading" })
+            );
+        }
+    }
+
+    #[test]
+    fn test_plain_text_errors_become_internal() {
+        let error = AppError::from("Failed to load node: disk full".to_string());
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "type": "Internal", "message": "Failed to load node: disk full" })
+        );
+    }
+
     #[test]
     fn test_all_error_variants() {
         let errors = vec![
             AppError::ServiceInitialization("init failed".to_string()),
+            AppError::ServiceInitializing("models loading".to_string()),
             AppError::DataStore("db error".to_string()),
             AppError::NlpEngine("nlp error".to_string()),
             AppError::NodeOperation("node error".to_string()),
@@ -116,6 +171,8 @@ mod tests {
             AppError::StateAccess("state error".to_string()),
             AppError::InvalidInput("invalid input".to_string()),
             AppError::NotFound("not found".to_string()),
+            AppError::PermissionDenied("permission denied".to_string()),
+            AppError::Cancelled("query-1".to_string()),
             AppError::Internal("internal error".to_string()),
         ];
 
@@ -0,0 +1,136 @@
+use std::io::Cursor;
+
+use exif::{Exif, Field, In, Reader, Tag, Value};
+use serde_json::{json, Map};
+
+/// Pull the EXIF fields the app cares about out of raw image bytes.
+///
+/// Returns `None` when the file has no EXIF block (typical for PNGs and screenshots) or
+/// when none of the recognised tags are present.
+pub fn extract_exif_data(image_bytes: &[u8]) -> Option<serde_json::Value> {
+    let exif = Reader::new()
+        .read_from_container(&mut Cursor::new(image_bytes))
+        .ok()?;
+
+    let mut data = Map::new();
+    if let Some(orientation) = uint_field(&exif, Tag::Orientation) {
+        data.insert("orientation".to_string(), json!(orientation));
+    }
+    for (tag, key) in [
+        (Tag::Make, "make"),
+        (Tag::Model, "model"),
+        (Tag::DateTimeOriginal, "datetime_original"),
+    ] {
+        if let Some(text) = ascii_field(&exif, tag) {
+            data.insert(key.to_string(), json!(text));
+        }
+    }
+    if let Some(gps) = gps_data(&exif) {
+        data.insert("gps".to_string(), gps);
+    }
+    if let Some(exposure) = exposure_data(&exif) {
+        data.insert("exposure".to_string(), exposure);
+    }
+
+    (!data.is_empty()).then_some(serde_json::Value::Object(data))
+}
+
+/// The stored EXIF orientation tag, if any
+pub fn orientation(exif_data: Option<&serde_json::Value>) -> Option<u32> {
+    exif_data?
+        .get("orientation")?
+        .as_u64()
+        .and_then(|o| u32::try_from(o).ok())
+}
+
+/// The stored GPS position as (latitude, longitude) in signed decimal degrees, if both
+/// are present
+pub fn gps_coordinates(exif_data: Option<&serde_json::Value>) -> Option<(f64, f64)> {
+    let gps = exif_data?.get("gps")?;
+    Some((
+        gps.get("latitude")?.as_f64()?,
+        gps.get("longitude")?.as_f64()?,
+    ))
+}
+
+/// Width and height as the image should be displayed. Orientations 5-8 rotate the image
+/// by 90 or 270 degrees, so the stored dimensions are swapped.
+pub fn oriented_dimensions(width: u32, height: u32, orientation: Option<u32>) -> (u32, u32) {
+    match orientation {
+        Some(5..=8) => (height, width),
+        _ => (width, height),
+    }
+}
+
+fn field(exif: &Exif, tag: Tag) -> Option<&Field> {
+    exif.get_field(tag, In::PRIMARY)
+}
+
+fn uint_field(exif: &Exif, tag: Tag) -> Option<u32> {
+    field(exif, tag)?.value.get_uint(0)
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    match &field(exif, tag)?.value {
+        Value::Ascii(parts) => {
+            let text = String::from_utf8_lossy(parts.first()?);
+            let text = text.trim_end_matches('\0').trim();
+            (!text.is_empty()).then(|| text.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn rational_field(exif: &Exif, tag: Tag) -> Option<f64> {
+    match &field(exif, tag)?.value {
+        Value::Rational(values) => values.first().map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+/// Degrees/minutes/seconds plus a hemisphere reference, as signed decimal degrees
+fn gps_coordinate(exif: &Exif, value_tag: Tag, ref_tag: Tag, negative_ref: &str) -> Option<f64> {
+    let Value::Rational(parts) = &field(exif, value_tag)?.value else {
+        return None;
+    };
+    let degrees = parts
+        .iter()
+        .take(3)
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(part, divisor)| part.to_f64() / divisor)
+        .sum::<f64>();
+
+    let negative = ascii_field(exif, ref_tag).is_some_and(|r| r.eq_ignore_ascii_case(negative_ref));
+    Some(if negative { -degrees } else { degrees })
+}
+
+fn gps_data(exif: &Exif) -> Option<serde_json::Value> {
+    let latitude = gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?;
+    let longitude = gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?;
+
+    let mut gps = json!({ "latitude": latitude, "longitude": longitude });
+    if let Some(altitude) = rational_field(exif, Tag::GPSAltitude) {
+        // Altitude ref 1 means below sea level
+        let below = uint_field(exif, Tag::GPSAltitudeRef) == Some(1);
+        gps["altitude"] = json!(if below { -altitude } else { altitude });
+    }
+    Some(gps)
+}
+
+fn exposure_data(exif: &Exif) -> Option<serde_json::Value> {
+    let mut exposure = Map::new();
+    for (tag, key) in [
+        (Tag::ExposureTime, "exposure_time"),
+        (Tag::FNumber, "f_number"),
+        (Tag::FocalLength, "focal_length"),
+    ] {
+        if let Some(value) = rational_field(exif, tag) {
+            exposure.insert(key.to_string(), json!(value));
+        }
+    }
+    if let Some(iso) = uint_field(exif, Tag::PhotographicSensitivity) {
+        exposure.insert("iso".to_string(), json!(iso));
+    }
+
+    (!exposure.is_empty()).then_some(serde_json::Value::Object(exposure))
+}
@@ -0,0 +1,53 @@
+/// Returned when a HEIC/HEIF file is dropped on a build without the `heic` feature
+pub const HEIF_UNSUPPORTED: &str =
+    "HEIC/HEIF images are not supported on this platform. Convert the image to JPEG or PNG.";
+
+/// `ftyp` major brands used by HEIC/HEIF still images
+const HEIF_BRANDS: &[&[u8; 4]] = &[
+    b"heic", b"heix", b"heim", b"heis", b"hevc", b"mif1", b"msf1",
+];
+
+/// Whether `bytes` start with an ISO-BMFF `ftyp` box carrying a HEIF brand
+pub fn is_heif(bytes: &[u8]) -> bool {
+    match (bytes.get(4..8), bytes.get(8..12)) {
+        (Some(b"ftyp"), Some(brand)) => HEIF_BRANDS.iter().any(|b| b.as_slice() == brand),
+        _ => false,
+    }
+}
+
+/// Decode the primary image of a HEIF container
+#[cfg(feature = "heic")]
+pub fn decode(bytes: &[u8]) -> Result<image::DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let context =
+        HeifContext::read_from_bytes(bytes).map_err(|e| format!("Invalid HEIF image: {}", e))?;
+    let handle = context
+        .primary_image_handle()
+        .map_err(|e| format!("Invalid HEIF image: {}", e))?;
+    let decoded = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(|e| format!("Failed to decode HEIF image: {}", e))?;
+
+    let planes = decoded.planes();
+    let plane = planes
+        .interleaved
+        .ok_or_else(|| "Decoded HEIF image has no RGBA plane".to_string())?;
+    let row_bytes = plane.width as usize * 4;
+    let pixels: Vec<u8> = plane
+        .data
+        .chunks(plane.stride)
+        .take(plane.height as usize)
+        .flat_map(|row| &row[..row_bytes])
+        .copied()
+        .collect();
+
+    image::RgbaImage::from_raw(plane.width, plane.height, pixels)
+        .map(image::DynamicImage::ImageRgba8)
+        .ok_or_else(|| "Decoded HEIF image has an unexpected size".to_string())
+}
+
+#[cfg(not(feature = "heic"))]
+pub fn decode(_bytes: &[u8]) -> Result<image::DynamicImage, String> {
+    Err(HEIF_UNSUPPORTED.to_string())
+}
@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use nodespace_core_types::Node;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// A node together with its ordered children, rebuilt from flat storage
+#[derive(Debug, Clone)]
+pub struct OutlineNode {
+    pub node: Node,
+    pub children: Vec<OutlineNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiblingConflictKind {
+    /// Several children claim the same predecessor
+    DuplicatePredecessor,
+    /// Predecessor pointers loop back on themselves
+    Cycle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiblingConflict {
+    pub kind: SiblingConflictKind,
+    pub before_sibling: Option<String>,
+    pub node_ids: Vec<String>,
+}
+
+/// Order siblings by following their `before_sibling` (predecessor) chain.
+///
+/// Siblings whose predecessor is missing start a chain; anything left over because of
+/// corrupt pointers is appended in its original order so no node is ever lost.
+pub fn order_siblings(siblings: Vec<Node>) -> Vec<Node> {
+    let ids: HashSet<String> = siblings.iter().map(|n| n.id.0.clone()).collect();
+
+    let mut successors: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut heads = Vec::new();
+    for (index, node) in siblings.iter().enumerate() {
+        match node.before_sibling.as_ref() {
+            Some(prev) if ids.contains(&prev.0) && prev.0 != node.id.0 => {
+                successors.entry(prev.0.clone()).or_default().push(index)
+            }
+            _ => heads.push(index),
+        }
+    }
+
+    let mut order = Vec::with_capacity(siblings.len());
+    let mut visited = vec![false; siblings.len()];
+    let mut stack: Vec<usize> = heads.into_iter().rev().collect();
+    while let Some(index) = stack.pop() {
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+        order.push(index);
+        if let Some(next) = successors.get(&siblings[index].id.0) {
+            stack.extend(next.iter().rev());
+        }
+    }
+    order.extend((0..siblings.len()).filter(|i| !visited[*i]));
+
+    let mut slots: Vec<Option<Node>> = siblings.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+/// Rebuild the outline for a set of nodes. Date nodes are treated as containers, so their
+/// children become the top level of the outline.
+pub fn build_outline(nodes: Vec<Node>) -> Vec<OutlineNode> {
+    let ids: HashSet<String> = nodes
+        .iter()
+        .filter(|n| n.r#type != "date")
+        .map(|n| n.id.0.clone())
+        .collect();
+
+    let mut by_parent: HashMap<Option<String>, Vec<Node>> = HashMap::new();
+    for node in nodes.into_iter().filter(|n| n.r#type != "date") {
+        let parent = node
+            .parent_id
+            .as_ref()
+            .map(|p| p.0.clone())
+            .filter(|p| ids.contains(p));
+        by_parent.entry(parent).or_default().push(node);
+    }
+
+    let mut visited = HashSet::new();
+    attach_children(None, &mut by_parent, &mut visited)
+}
+
+fn attach_children(
+    parent: Option<String>,
+    by_parent: &mut HashMap<Option<String>, Vec<Node>>,
+    visited: &mut HashSet<String>,
+) -> Vec<OutlineNode> {
+    let siblings = by_parent.remove(&parent).unwrap_or_default();
+
+    let mut outline = Vec::new();
+    for node in order_siblings(siblings) {
+        if !visited.insert(node.id.0.clone()) {
+            continue;
+        }
+        let children = attach_children(Some(node.id.0.clone()), by_parent, visited);
+        outline.push(OutlineNode { node, children });
+    }
+    outline
+}
+
+/// The direct children of `parent_id` in sibling order; grandchildren are left out
+pub fn direct_children(nodes: Vec<Node>, parent_id: &str) -> Vec<Node> {
+    let children = nodes
+        .into_iter()
+        .filter(|n| n.parent_id.as_ref().is_some_and(|p| p.0 == parent_id))
+        .collect();
+    order_siblings(children)
+}
+
+/// The root node plus all of its descendants, found by following `parent_id` links.
+/// Returns an empty list when the root isn't present in `nodes`.
+pub fn collect_subtree(nodes: Vec<Node>, root_id: &str) -> Vec<Node> {
+    let mut children: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut root = None;
+    for (index, node) in nodes.iter().enumerate() {
+        if node.id.0 == root_id {
+            root = Some(index);
+        } else if let Some(parent) = node.parent_id.as_ref() {
+            children.entry(parent.0.clone()).or_default().push(index);
+        }
+    }
+    let Some(root) = root else {
+        return Vec::new();
+    };
+
+    let mut members = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(index) = stack.pop() {
+        if !seen.insert(index) {
+            continue;
+        }
+        members.push(index);
+        if let Some(next) = children.get(&nodes[index].id.0) {
+            stack.extend(next.iter().rev());
+        }
+    }
+
+    let mut slots: Vec<Option<Node>> = nodes.into_iter().map(Some).collect();
+    members
+        .into_iter()
+        .filter_map(|i| slots[i].take())
+        .collect()
+}
+
+/// Report ambiguous ordering among siblings: children sharing a `before_sibling` value
+/// (including several children with none) and predecessor pointer cycles.
+pub fn find_sibling_conflicts(siblings: &[Node]) -> Vec<SiblingConflict> {
+    let mut conflicts = Vec::new();
+
+    let mut by_predecessor: HashMap<Option<&str>, Vec<&str>> = HashMap::new();
+    for node in siblings {
+        by_predecessor
+            .entry(node.before_sibling.as_ref().map(|p| p.0.as_str()))
+            .or_default()
+            .push(node.id.0.as_str());
+    }
+    let mut duplicates: Vec<_> = by_predecessor
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .collect();
+    duplicates.sort();
+    for (predecessor, ids) in duplicates {
+        conflicts.push(SiblingConflict {
+            kind: SiblingConflictKind::DuplicatePredecessor,
+            before_sibling: predecessor.map(|p| p.to_string()),
+            node_ids: ids.into_iter().map(|id| id.to_string()).collect(),
+        });
+    }
+
+    let predecessor_of: HashMap<&str, &str> = siblings
+        .iter()
+        .filter_map(|n| {
+            n.before_sibling
+                .as_ref()
+                .map(|p| (n.id.0.as_str(), p.0.as_str()))
+        })
+        .collect();
+    let mut reported: HashSet<&str> = HashSet::new();
+    for node in siblings {
+        let start = node.id.0.as_str();
+        if reported.contains(start) {
+            continue;
+        }
+
+        let mut path = Vec::new();
+        let mut current = start;
+        loop {
+            if let Some(pos) = path.iter().position(|id| *id == current) {
+                let mut cycle: Vec<&str> = path[pos..].to_vec();
+                if cycle.iter().all(|id| !reported.contains(id)) {
+                    reported.extend(cycle.iter().copied());
+                    cycle.sort();
+                    conflicts.push(SiblingConflict {
+                        kind: SiblingConflictKind::Cycle,
+                        before_sibling: None,
+                        node_ids: cycle.into_iter().map(|id| id.to_string()).collect(),
+                    });
+                }
+                break;
+            }
+            path.push(current);
+            match predecessor_of.get(current) {
+                Some(next) => current = *next,
+                None => break,
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// The ancestors of a node, nearest first, found by following `parent_id` links up to the
+/// root. A parent that isn't present in `nodes` ends the chain.
+pub fn ancestor_chain<'a>(nodes: &'a [Node], node_id: &str) -> AppResult<Vec<&'a Node>> {
+    let by_id: HashMap<&str, &Node> = nodes.iter().map(|n| (n.id.0.as_str(), n)).collect();
+    let mut current = *by_id
+        .get(node_id)
+        .ok_or_else(|| AppError::NotFound(format!("Node {}", node_id)))?;
+
+    let mut seen = HashSet::from([node_id]);
+    let mut chain = Vec::new();
+    while let Some(parent_id) = current.parent_id.as_ref() {
+        let Some(parent) = by_id.get(parent_id.0.as_str()) else {
+            break;
+        };
+        if !seen.insert(parent_id.0.as_str()) {
+            return Err(AppError::NodeOperation(format!(
+                "Cycle detected in parent chain of node {}",
+                node_id
+            )));
+        }
+        chain.push(*parent);
+        current = *parent;
+    }
+    Ok(chain)
+}
+
+/// A node's ancestors ordered root-first, for breadcrumb display. Empty for a root-level
+/// node.
+pub fn breadcrumb_trail(nodes: &[Node], node_id: &str) -> AppResult<Vec<Node>> {
+    let mut trail: Vec<Node> = ancestor_chain(nodes, node_id)?
+        .into_iter()
+        .cloned()
+        .collect();
+    trail.reverse();
+    Ok(trail)
+}
+
+/// Reject making `new_parent_id` the parent of `node_id` when the new parent is the node
+/// itself or one of its descendants. `nodes` must include the new parent's ancestors.
+pub fn check_reparent(nodes: &[Node], node_id: &str, new_parent_id: &str) -> AppResult<()> {
+    let creates_cycle = new_parent_id == node_id
+        || ancestor_chain(nodes, new_parent_id)?
+            .iter()
+            .any(|ancestor| ancestor.id.0 == node_id);
+    if creates_cycle {
+        return Err(AppError::NodeOperation(format!(
+            "Cannot move node {} under {}: the new parent is the node itself or one of its \
+             descendants",
+            node_id, new_parent_id
+        )));
+    }
+    Ok(())
+}
@@ -1,5 +1,18 @@
+mod api;
+mod bundle;
+mod config;
+mod embeddings;
 mod error;
+mod exif_info;
+mod heif;
+mod hierarchy;
 mod logging;
+mod markdown;
+mod metrics;
+mod operations;
+mod render;
+mod schema;
+mod settings;
 
 #[cfg(test)]
 mod tests;
@@ -7,11 +20,15 @@ mod tests;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_opener::OpenerExt;
 use tokio::sync::Mutex;
 
+use crate::config::AppConfig;
 use crate::error::AppError;
 use crate::logging::*;
+use crate::settings::WorkspaceSettings;
 
 use chrono::NaiveDate;
 use nodespace_core_logic::{CoreLogic, HierarchyComputation, NodeSpaceService};
@@ -33,12 +50,25 @@ pub struct SearchResult {
     pub snippet: String,
 }
 
+/// One page of search results. More pages exist while
+/// `offset + results.len() < total_available`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    pub offset: usize,
+    pub total_available: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageData {
     pub file_path: String,
     pub metadata: ImageMetadata,
     pub embeddings: Vec<f32>,
+    /// The whole original as a data URL. This can run to megabytes, so lists should show
+    /// `thumbnail_url` and load the original only when the image is opened.
     pub blob_url: String,
+    /// JPEG data URL downscaled to fit `THUMBNAIL_MAX_DIMENSION`
+    pub thumbnail_url: String,
     pub dimensions: (u32, u32),
 }
 
@@ -50,10 +80,61 @@ pub struct ImageMetadata {
     pub width: u32,
     pub height: u32,
     pub exif_data: Option<serde_json::Value>,
+    /// GPS position from `exif_data`, in signed decimal degrees, kept top-level so nodes
+    /// can be filtered by location
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    /// Place name for `lat`/`lng`. No offline reverse geocoder is bundled yet, so this is
+    /// always `None` for now.
+    pub location: Option<String>,
     pub ai_description: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Set when the image embedding could not be generated. It is stored with the rest of
+    /// the metadata, so `retry_failed_embeddings` picks the node up later.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub embedding_failed: bool,
+}
+
+/// A dropped file that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileError {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Outcome of `process_dropped_files`. Every dropped path lands in exactly one list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DropResult {
+    pub succeeded: Vec<ImageData>,
+    pub failed: Vec<FileError>,
 }
 
+/// Which tasks `get_tasks` returns. Unset fields don't filter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    pub completed: Option<bool>,
+    /// YYYY-MM-DD; only tasks due strictly before this date, so tasks without a due date
+    /// are left out
+    pub due_before: Option<String>,
+    /// One of low, medium or high
+    pub priority: Option<String>,
+}
+
+/// What changed on a date since a version the frontend last saw, from
+/// `get_nodes_for_date_since`. The store keeps no change counter, so a version is the
+/// newest `updated_at` in milliseconds since the epoch. Deletions leave no timestamp
+/// behind, so `node_ids` lists every node the date still holds: any node the frontend
+/// has that isn't listed was deleted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DateDelta {
+    pub version: u64,
+    pub changed: Vec<Node>,
+    pub node_ids: Vec<String>,
+}
+
+/// `FileError` reason for dropped files that aren't a supported image format
+const UNSUPPORTED_FILE_TYPE: &str = "unsupported type";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MultimodalSearchConfig {
     pub semantic_weight: f32,
@@ -62,41 +143,434 @@ pub struct MultimodalSearchConfig {
     pub min_similarity_threshold: f32,
 }
 
-type NodeSpaceServiceType =
-    Arc<Mutex<Option<Arc<NodeSpaceService<LanceDataStore, LocalNLPEngine>>>>>;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationResult {
+    pub valid: bool,
+    pub missing: Vec<String>,
+    pub invalid: Vec<FieldIssue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldIssue {
+    pub field: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrySummary {
+    pub attempted: usize,
+    pub succeeded: usize,
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub processed: usize,
+    pub total: usize,
+    pub node_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkReplaceResult {
+    pub affected_node_ids: Vec<String>,
+    pub replacements: usize,
+    pub dry_run: bool,
+}
+
+/// Outcome of `tag_search_results`: how many nodes were (or, on a dry run, would be)
+/// tagged, and on a dry run which ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagSearchResult {
+    pub count: usize,
+    pub preview: Vec<String>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillSummary {
+    pub total: usize,
+    pub generated: usize,
+    pub skipped_missing: usize,
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cluster {
+    pub label_node_id: String,
+    pub label: String,
+    pub member_ids: Vec<String>,
+}
+
+/// An installed generation model, as listed by `list_available_models`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub name: String,
+    /// Total size of the model directory on disk
+    pub size_bytes: u64,
+    /// Whether generation currently uses this model
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelLimits {
+    pub model: Option<String>,
+    pub context_window_tokens: usize,
+    pub max_input_tokens: usize,
+    pub max_output_tokens: usize,
+    /// False when the model isn't recognised and conservative defaults were returned
+    pub known: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateMismatch {
+    pub node_id: String,
+    pub stored_date: String,
+    pub root_id: String,
+    pub root_date: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccelerationInfo {
+    /// One of "cpu", "cuda", "metal", or "unknown"
+    pub backend: String,
+    pub gpu_active: bool,
+    pub device_memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Messages sent over the `process_query_stream` channel: tokens as they are generated,
+/// then a single `done` carrying the full response with sources and confidence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum QueryStreamEvent {
+    Token { text: String },
+    Done { response: QueryResponse },
+}
+
+/// One node in a `create_nodes_for_date_batch` call. A `before_sibling_id` may point at a
+/// node created earlier in the same batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeCreateRequest {
+    pub node_id: String,
+    pub content: String,
+    pub parent_id: Option<String>,
+    pub before_sibling_id: Option<String>,
+    pub node_type: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Upper bound on nodes accepted by a single batch create
+const MAX_BATCH_CREATE: usize = 500;
+
+/// Most parent links followed when walking from a node up to its root
+const MAX_ANCESTOR_WALK: usize = 1000;
+
+/// Deepest subtree, in levels including the root, that `duplicate_node` will copy
+const MAX_DUPLICATE_DEPTH: usize = 32;
+
+/// Text embedded to check the dimension the embedding model actually produces
+const EMBEDDING_SAMPLE_TEXT: &str = "NodeSpace embedding dimension check";
+
+/// Most nodes a subtree may hold for `search_in_subtree` to search it
+const MAX_SUBTREE_SEARCH_NODES: usize = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphVertex {
+    pub id: String,
+    pub label: String,
+    pub node_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphEdgeKind {
+    /// Parent to child
+    Hierarchy,
+    /// Referencing node to the node listed in its `references` metadata
+    Reference,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: GraphEdgeKind,
+}
+
+/// Vertices and edges for the graph view. When the vault has more nodes than the cap, only
+/// the most recently updated are included and `truncated` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    pub vertices: Vec<GraphVertex>,
+    pub edges: Vec<GraphEdge>,
+    pub total_nodes: usize,
+    pub truncated: bool,
+}
+
+/// Most vertices returned by `get_graph_snapshot`; beyond this a force layout stops being usable
+const MAX_GRAPH_VERTICES: usize = 2000;
+
+/// How long `reconfigure_service` waits for running commands to release the old service
+const SERVICE_RELEASE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Returned by `create_image_node` when the user closes the picker without choosing a file,
+/// so the frontend can ignore it instead of showing an error.
+const NO_FILE_SELECTED: &str = "No file selected";
+
+/// Extensions offered by the insert-image file picker
+const IMAGE_DIALOG_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Event emitted after a node write succeeds, so other windows on the same date can refresh
+const NODE_CHANGED_EVENT: &str = "node-changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeChangeKind {
+    Created,
+    Updated,
+    Moved,
+    Deleted,
+}
+
+/// Payload of `node-changed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeChangedEvent {
+    pub operation: NodeChangeKind,
+    pub node_id: String,
+    /// The affected day as YYYY-MM-DD, when it could be determined
+    pub date: Option<String>,
+}
+
+/// Payload of `export-chunk`: one batch of nodes for a date, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportChunkEvent {
+    pub channel_id: String,
+    pub date: String,
+    pub ndjson: String,
+    pub node_count: usize,
+}
+
+/// Payload of `export-done`, sent once every chunk has been emitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportDoneEvent {
+    pub channel_id: String,
+    pub total_nodes: usize,
+    pub dates_exported: usize,
+}
+
+/// Nodes per `export-chunk` event
+const EXPORT_CHUNK_SIZE: usize = 200;
+
+/// Longest range `stream_export_range` accepts, in days
+const MAX_EXPORT_RANGE_DAYS: i64 = 3660;
+
+/// Longest range `count_nodes_for_range` accepts, in days
+const MAX_COUNT_RANGE_DAYS: i64 = 366;
+
+/// Snapshot of background service initialization, for the frontend to poll
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub initialized: bool,
+    pub model_loaded: bool,
+    pub db_connected: bool,
+    pub detail: Option<String>,
+}
+
+impl ServiceStatus {
+    fn not_started(detail: &str) -> Self {
+        Self {
+            initialized: false,
+            model_loaded: false,
+            db_connected: false,
+            detail: Some(detail.to_string()),
+        }
+    }
+
+    /// The data store is opened when the service is created; models load in the background
+    fn created(model_loaded: bool) -> Self {
+        Self {
+            initialized: model_loaded,
+            model_loaded,
+            db_connected: true,
+            detail: (!model_loaded).then(|| "Loading AI models in background".to_string()),
+        }
+    }
+}
+
+/// How the frontend's view of a node's position compares with what is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    pub node_id: String,
+    pub stored_parent_id: Option<String>,
+    pub expected_parent_id: Option<String>,
+    pub stored_before_sibling: Option<String>,
+    pub expected_before_sibling: Option<String>,
+    pub parent_matches: bool,
+    pub before_sibling_matches: bool,
+    pub repaired: bool,
+}
+
+impl ReconcileReport {
+    pub fn in_sync(&self) -> bool {
+        self.parent_matches && self.before_sibling_matches
+    }
+}
+
+/// Whether `upsert_node` created a new node or changed an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpsertOutcome {
+    Inserted,
+    Updated,
+}
+
+/// Which stored fields an upsert of an existing node has to write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpsertChanges {
+    pub content: bool,
+    pub metadata: bool,
+    pub parent: bool,
+    pub sibling_order: bool,
+}
+
+/// Deepest result a paged search can reach (`offset + limit`), and how many matches are
+/// fetched to count `total_available`
+const MAX_SEARCH_WINDOW: usize = 1000;
+
+/// Longest edge, in pixels, of generated image thumbnails
+const THUMBNAIL_MAX_DIMENSION: u32 = 256;
+
+/// Metadata flag set on nodes whose embedding could not be generated at ingestion time
+const EMBEDDING_FAILED_KEY: &str = "embedding_failed";
+
+/// Type string the frontend uses for AI chat nodes
+const AI_CHAT_NODE_TYPE: &str = "ai-chat";
+
+/// Metadata key holding a node's manual sort rank among its siblings
+const SORT_KEY: &str = "sort_key";
+
+/// Metadata key holding a node's RFC3339 reminder time
+const REMIND_AT_KEY: &str = "remind_at";
+
+/// Metadata flag set once a due reminder has been dismissed
+const REMINDER_DISMISSED_KEY: &str = "reminder_dismissed";
+
+/// Type string for task nodes
+const TASK_NODE_TYPE: &str = "task";
+
+/// Metadata flag marking a task as done
+const COMPLETED_KEY: &str = "completed";
+
+/// Metadata key holding the RFC3339 time a task was last completed
+const COMPLETED_AT_KEY: &str = "completed_at";
+
+/// Metadata key holding a task's YYYY-MM-DD due date
+const DUE_DATE_KEY: &str = "due_date";
+
+/// Metadata key holding a task's priority, one of `TASK_PRIORITIES`
+const PRIORITY_KEY: &str = "priority";
+
+/// Task priorities, most urgent first
+const TASK_PRIORITIES: [&str; 3] = ["high", "medium", "low"];
+
+type NodeSpaceServiceImpl = NodeSpaceService<LanceDataStore, LocalNLPEngine>;
+
+type NodeSpaceServiceType = Arc<Mutex<Option<Arc<NodeSpaceServiceImpl>>>>;
+
+/// Earliest and latest days holding a node, as returned by `get_date_bounds`
+type DateBounds = (Option<NaiveDate>, Option<NaiveDate>);
 
 pub struct AppState {
     pub nodespace_service: NodeSpaceServiceType,
+    pub settings: Arc<Mutex<WorkspaceSettings>>,
+    pub query_latencies: Arc<Mutex<metrics::LatencyTracker>>,
+    /// Paths the service is (re)created with; see `reconfigure_service`
+    pub config: Arc<Mutex<AppConfig>>,
+    pub operations: Arc<operations::OperationRegistry>,
+    /// Last `get_date_bounds` result, widened as nodes are created; `None` until computed
+    pub date_bounds: Arc<Mutex<Option<DateBounds>>>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             nodespace_service: Arc::new(Mutex::new(None)),
+            settings: Arc::new(Mutex::new(WorkspaceSettings::load())),
+            query_latencies: Arc::new(Mutex::new(metrics::LatencyTracker::default())),
+            config: Arc::new(Mutex::new(AppConfig::new())),
+            operations: Arc::new(operations::OperationRegistry::default()),
+            date_bounds: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl AppState {
+    /// A copy of the active configuration
+    pub async fn config(&self) -> AppConfig {
+        self.config.lock().await.clone()
+    }
+
+    /// Widen the cached date bounds to cover `date`, once a node has been created on it
+    pub async fn note_node_date(&self, date: NaiveDate) {
+        if let Some(bounds) = self.date_bounds.lock().await.as_mut() {
+            *bounds = widen_date_bounds(*bounds, date);
         }
     }
+
+    /// Drop the cached date bounds, for writes whose date isn't known
+    pub async fn forget_date_bounds(&self) {
+        *self.date_bounds.lock().await = None;
+    }
+
+    /// The shared service, initialized on first use. The lock is only held while the
+    /// service is looked up or created, so commands don't wait on each other's work.
+    pub async fn get_service(&self) -> Result<Arc<NodeSpaceServiceImpl>, AppError> {
+        get_or_init(&self.nodespace_service, || {
+            initialize_nodespace_service(self)
+        })
+        .await
+    }
+}
+
+/// The value in `slot`, running `init` to fill it if it is empty. Concurrent callers wait
+/// on the lock, so `init` runs at most once per successful initialization.
+async fn get_or_init<S, E, F, Fut>(slot: &Mutex<Option<Arc<S>>>, init: F) -> Result<Arc<S>, E>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Arc<S>, E>>,
+{
+    let mut guard = slot.lock().await;
+    if guard.is_none() {
+        *guard = Some(init().await?);
+    }
+    Ok(guard.as_ref().unwrap().clone())
 }
 
 async fn initialize_nodespace_service(
-) -> Result<Arc<NodeSpaceService<LanceDataStore, LocalNLPEngine>>, String> {
+    state: &AppState,
+) -> Result<Arc<NodeSpaceService<LanceDataStore, LocalNLPEngine>>, AppError> {
     log::info!("Initializing NodeSpaceService");
 
-    let db_path = "/Users/malibio/nodespace/data/lance_db";
-    let models_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .parent()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("models");
+    let config = state.config().await;
+    let (db_path, models_dir) = service_paths(&config)?;
 
     log::info!("Database path: {}", db_path);
-    log::info!("Models directory: {}", models_dir.display());
+    log::info!("Models directory: {}", models_dir);
+
+    let model = state.settings.lock().await.default_model.clone();
+    config.check_model(model.as_deref().unwrap_or(config::DEFAULT_MODEL))?;
 
-    let models_dir_str = models_dir.to_str()
-        .ok_or_else(|| "Invalid models directory path".to_string())?;
-    let service = NodeSpaceService::create_with_background_init(db_path, Some(models_dir_str))
+    let service = NodeSpaceService::create_with_background_init(db_path, Some(models_dir))
         .await
-        .map_err(|e| format!("Failed to initialize NodeSpaceService: {}", e))?;
+        .map_err(|e| {
+            AppError::ServiceInitialization(format!("Failed to initialize NodeSpaceService: {}", e))
+        })?;
 
     log_service_init("NodeSpaceService");
     log_service_ready("NodeSpaceService");
@@ -105,8 +579,196 @@ async fn initialize_nodespace_service(
     Ok(service)
 }
 
+/// Wait until nothing but its slot holds `service`, so commands that took a clone before
+/// a swap finish against it. Gives up after `timeout`.
+async fn wait_for_release<S>(
+    service: Option<&Arc<S>>,
+    timeout: std::time::Duration,
+) -> Result<(), AppError> {
+    let Some(service) = service else {
+        return Ok(());
+    };
+    let deadline = tokio::time::Instant::now() + timeout;
+    while Arc::strong_count(service) > 1 {
+        if tokio::time::Instant::now() >= deadline {
+            return Err(AppError::StateAccess(format!(
+                "{} running commands are still using the service; try again once they finish",
+                Arc::strong_count(service) - 1
+            )));
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    Ok(())
+}
+
+/// Switch to `new_config` and drop the current service so the next command initializes a
+/// fresh one from it. An invalid config is rejected and the current service kept.
+fn swap_config<S>(
+    config: &mut AppConfig,
+    service: &mut Option<S>,
+    new_config: AppConfig,
+) -> Result<(), AppError> {
+    new_config.validate()?;
+    *config = new_config;
+    *service = None;
+    Ok(())
+}
+
+/// The database and models paths the service is created with, once the config has
+/// passed validation
+fn service_paths(config: &AppConfig) -> Result<(&str, &str), AppError> {
+    let invalid =
+        |e: AppError| AppError::ServiceInitialization(format!("Invalid configuration: {}", e));
+    config.validate().map_err(invalid)?;
+    Ok((
+        config.database_path_str().map_err(invalid)?,
+        config.models_path_str().map_err(invalid)?,
+    ))
+}
+
+/// Where images imported from bundles are copied, alongside the database directory
+fn imported_assets_directory(database_path: &std::path::Path) -> std::path::PathBuf {
+    database_path
+        .parent()
+        .unwrap_or(database_path)
+        .join(bundle::ASSETS_DIR)
+}
+
+/// Models installed in `models_dir`: every subdirectory that contains a `model.onnx`.
+fn available_models(models_dir: &std::path::Path) -> Vec<String> {
+    let mut models: Vec<String> = std::fs::read_dir(models_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().join(config::MODEL_FILE).is_file())
+                .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    models.sort();
+    models
+}
+
+/// The installed models with their sizes, flagging `active` as the one in use
+fn installed_models(models_dir: &std::path::Path, active: Option<&str>) -> Vec<ModelInfo> {
+    available_models(models_dir)
+        .into_iter()
+        .map(|name| ModelInfo {
+            size_bytes: directory_size(&models_dir.join(&name)),
+            active: active == Some(name.as_str()),
+            name,
+        })
+        .collect()
+}
+
+/// Total size of the files under `dir`; unreadable entries count as zero
+fn directory_size(dir: &std::path::Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| match entry.file_type() {
+                    Ok(kind) if kind.is_dir() => directory_size(&entry.path()),
+                    Ok(_) => entry.metadata().map_or(0, |m| m.len()),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn validate_model_name(model: &str, available: &[String]) -> Result<(), AppError> {
+    if model.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Model name cannot be empty".to_string(),
+        ));
+    }
+    if !available.iter().any(|m| m == model) {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown model '{}'. Available models: {}",
+            model,
+            available.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Published limits for the models NodeSpace ships with; anything else gets conservative
+/// defaults flagged as unknown.
+fn model_limits_for(model: Option<&str>) -> ModelLimits {
+    let known = match model.map(|m| m.to_lowercase()) {
+        Some(m) if m.starts_with("gemma-3-1b") || m.starts_with("gemma3:1b") => {
+            Some((32_768, 8_192))
+        }
+        Some(m)
+            if m.starts_with("gemma-3-4b")
+                || m.starts_with("gemma-3-12b")
+                || m.starts_with("gemma3:4b")
+                || m.starts_with("gemma3:12b") =>
+        {
+            Some((131_072, 8_192))
+        }
+        _ => None,
+    };
+
+    match known {
+        Some((context, output)) => ModelLimits {
+            model: model.map(|m| m.to_string()),
+            context_window_tokens: context,
+            max_input_tokens: context - output,
+            max_output_tokens: output,
+            known: true,
+        },
+        None => ModelLimits {
+            model: model.map(|m| m.to_string()),
+            context_window_tokens: 2_048,
+            max_input_tokens: 1_536,
+            max_output_tokens: 512,
+            known: false,
+        },
+    }
+}
+
+fn validate_generation_request(
+    prompt: &str,
+    model: Option<&str>,
+    available: &[String],
+) -> Result<(), AppError> {
+    if prompt.trim().is_empty() {
+        return Err(AppError::InvalidInput("Prompt cannot be empty".to_string()));
+    }
+    if let Some(model) = model {
+        validate_model_name(model, available)?;
+    }
+    Ok(())
+}
+
+/// Normalise the execution provider name reported by the NLP engine. ONNX Runtime's
+/// CoreML provider runs on Apple's Metal GPU stack, so it is reported as "metal".
+fn acceleration_from_backend(backend: Option<&str>) -> AccelerationInfo {
+    let backend = match backend.map(|b| b.trim().to_lowercase()).as_deref() {
+        Some("cpu") => "cpu",
+        Some("cuda") | Some("cudaexecutionprovider") => "cuda",
+        Some("metal") | Some("coreml") | Some("coremlexecutionprovider") => "metal",
+        _ => "unknown",
+    };
+
+    AccelerationInfo {
+        backend: backend.to_string(),
+        gpu_active: matches!(backend, "cuda" | "metal"),
+        device_memory_bytes: None,
+    }
+}
+
+/// A per-call model wins over the workspace default
+fn resolve_model(requested: Option<String>, settings: &WorkspaceSettings) -> Option<String> {
+    requested
+        .filter(|m| !m.trim().is_empty())
+        .or_else(|| settings.default_model.clone())
+}
+
 #[tauri::command]
-async fn greet(name: String) -> Result<String, String> {
+async fn greet(name: String) -> Result<String, AppError> {
     Ok(format!("Hello, {}! Welcome to NodeSpace.", name))
 }
 
@@ -115,33 +777,29 @@ async fn create_knowledge_node(
     content: String,
     metadata: HashMap<String, serde_json::Value>,
     state: State<'_, AppState>,
-) -> Result<NodeId, String> {
+) -> Result<NodeId, AppError> {
     log_command(
         "create_knowledge_node",
         &format!("content_len: {}", content.len()),
     );
 
     if content.trim().is_empty() {
-        return Err(AppError::InvalidInput("Content cannot be empty".to_string()).into());
-    }
-
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
+        return Err(AppError::InvalidInput(
+            "Content cannot be empty".to_string(),
+        ));
     }
-    let service = service_guard.as_ref().unwrap();
 
     let metadata_value = serde_json::Value::Object(metadata.into_iter().collect());
+    check_metadata_size(&metadata_value, state.config().await.max_metadata_bytes)?;
+
+    let service = &state.get_service().await?;
+
     let node_id = service
         .create_knowledge_node(&content, metadata_value)
         .await
-        .map_err(|e| {
-            if e.to_string().contains("Service not ready: Initializing") {
-                "Service is initializing. Please try again in a moment.".to_string()
-            } else {
-                format!("Failed to create knowledge node: {}", e)
-            }
-        })?;
+        .map_err(|e| classify_service_error(&e, "Failed to create knowledge node"))?;
+    flag_missing_embedding(&state, service, &node_id).await;
+    state.forget_date_bounds().await;
 
     log::info!("Created knowledge node: {}", node_id);
     Ok(node_id)
@@ -152,153 +810,276 @@ async fn update_node(
     node_id: String,
     content: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log_command(
         "update_node",
         &format!("node_id: {}, content_len: {}", node_id, content.len()),
     );
 
     if content.trim().is_empty() {
-        return Err(AppError::InvalidInput("Content cannot be empty".to_string()).into());
+        return Err(AppError::InvalidInput(
+            "Content cannot be empty".to_string(),
+        ));
     }
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     let node_id_obj = NodeId::from_string(node_id.clone());
 
     service
         .update_node(&node_id_obj, &content)
         .await
-        .map_err(|e| format!("Failed to update node: {}", e))?;
+        .map_err(|e| classify_service_error(&e, "Failed to update node"))?;
 
     log::info!("Updated node: {}", node_id);
     Ok(())
 }
 
+/// Answer a question with retrieval. Generation can take seconds, so only the service
+/// handle is held while it runs, never the service lock. The query runs under
+/// `operation_id` until it finishes or `cancel_operation` stops it.
 #[tauri::command]
 async fn process_query(
     question: String,
+    model: Option<String>,
+    operation_id: String,
     state: State<'_, AppState>,
-) -> Result<QueryResponse, String> {
-    log_command("process_query", &format!("question: {}", question));
+) -> Result<QueryResponse, AppError> {
+    let _timer = log_command_timed(
+        "process_query",
+        &format!(
+            "question: {}, model: {:?}, operation_id: {}",
+            question, model, operation_id
+        ),
+    );
 
     if question.trim().is_empty() {
-        return Err(AppError::InvalidInput("Question cannot be empty".to_string()).into());
+        return Err(AppError::InvalidInput(
+            "Question cannot be empty".to_string(),
+        ));
     }
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
+    let model = resolve_model(model, &*state.settings.lock().await);
+    if let Some(model) = model.as_deref() {
+        validate_model_name(model, &available_models(&state.config().await.models_path))?;
     }
-    let service = service_guard.as_ref().unwrap();
 
-    log::info!("Processing query: {}", question);
+    let service = &state.get_service().await?;
 
-    let query_response = match service.process_query(&question).await {
-        Ok(response) => response,
-        Err(e) if e.to_string().contains("Service not ready: Initializing") => {
-            log::info!("Services initializing, retrying in 2 seconds...");
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-            
-            service.process_query(&question).await.map_err(|retry_e| {
-                if retry_e.to_string().contains("Service not ready: Initializing") {
-                    "Services are still initializing. Please try again.".to_string()
-                } else {
-                    format!("Failed to process query: {}", retry_e)
-                }
-            })?
-        }
-        Err(e) => return Err(format!("Failed to process query: {}", e)),
-    };
+    log::info!("Processing query: {}", question);
 
-    let search_results = service
-        .semantic_search(&question, 5)
-        .await
-        .unwrap_or_default();
+    let query = answer_question(&state, service, &question, model.as_deref());
+    let response = state.operations.run(&operation_id, query).await?;
 
-    let source_results: Vec<SearchResult> = search_results.into_iter().map(|search_result| {
-        let snippet = if let Some(content_str) = search_result.node.content.as_str() {
-            let snippet_len = content_str.len().min(100);
-            format!("{}...", &content_str[..snippet_len])
-        } else {
-            "...".to_string()
-        };
+    log::info!("Query processed successfully");
+    Ok(response)
+}
 
-        SearchResult {
-            node: search_result.node,
-            score: search_result.score as f64,
-            snippet,
+/// Run a question through retrieval and generation, retrying while the services finish
+/// initializing, and record the generation latency
+async fn answer_question(
+    state: &AppState,
+    service: &NodeSpaceServiceImpl,
+    question: &str,
+    model: Option<&str>,
+) -> Result<QueryResponse, AppError> {
+    let run_query = || async move {
+        match model {
+            Some(model) => service.process_query_with_model(question, model).await,
+            None => service.process_query(question).await,
         }
-    }).collect();
+    };
 
-    let response = QueryResponse {
+    let started = std::time::Instant::now();
+    let query_response =
+        with_init_retry(run_query, "Failed to process query", &state.config().await).await?;
+    state.query_latencies.lock().await.record(started.elapsed());
+
+    Ok(QueryResponse {
         answer: query_response.answer,
-        sources: source_results,
+        sources: query_sources(service, question).await,
         confidence: query_response.confidence as f64,
-    };
+    })
+}
 
-    log::info!("Query processed successfully");
+/// Re-ask the question stored on an AI chat node against the current knowledge base and
+/// store the new answer on the node. The original question is kept.
+#[tauri::command]
+async fn regenerate_ai_chat(
+    node_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<QueryResponse, AppError> {
+    log_command("regenerate_ai_chat", &format!("node_id: {}", node_id));
+
+    let service = &state.get_service().await?;
+    let node = load_node(service, &node_id).await?;
+    if !render::is_ai_chat_node(&node) {
+        return Err(AppError::InvalidInput(format!(
+            "Node {} is not an AI chat node",
+            node_id
+        )));
+    }
+    let question = node
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("question"))
+        .and_then(|q| q.as_str())
+        .filter(|q| !q.trim().is_empty())
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("AI chat node {} has no stored question", node_id))
+        })?
+        .to_string();
+
+    let model = resolve_model(None, &*state.settings.lock().await);
+    log::info!("Regenerating answer for AI chat node {}", node_id);
+    let response = answer_question(&state, service, &question, model.as_deref()).await?;
+
+    let metadata = with_regenerated_answer(node.metadata.as_ref(), &response);
+    store_node_metadata(&state, service, &node.id, metadata).await?;
+
+    let date = lookup_node_date(service, &node_id).await;
+    emit_node_changed(&app, NodeChangeKind::Updated, &node_id, date);
     Ok(response)
 }
 
+/// Stop an in-flight operation, such as a `process_query` started with this ID. Its command
+/// then fails with `AppError::Cancelled`.
 #[tauri::command]
-async fn semantic_search(
-    query: String,
-    limit: usize,
+async fn cancel_operation(
+    operation_id: String,
     state: State<'_, AppState>,
-) -> Result<Vec<SearchResult>, String> {
+) -> Result<(), AppError> {
     log_command(
-        "semantic_search",
-        &format!("query: {}, limit: {}", query, limit),
+        "cancel_operation",
+        &format!("operation_id: {}", operation_id),
     );
 
-    if query.trim().is_empty() {
-        return Err(AppError::InvalidInput("Search query cannot be empty".to_string()).into());
+    if !state.operations.cancel(&operation_id) {
+        return Err(AppError::NotFound(format!("Operation {}", operation_id)));
     }
 
-    if limit == 0 || limit > 100 {
-        return Err(AppError::InvalidInput("Limit must be between 1 and 100".to_string()).into());
-    }
+    log::info!("Cancelled operation {}", operation_id);
+    Ok(())
+}
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
-
-    log::info!("Performing semantic search: {} (limit: {})", query, limit);
-
-    let search_results = match service.semantic_search(&query, limit).await {
-        Ok(results) => results,
-        Err(e) if e.to_string().contains("Service not ready: Initializing") => {
-            log::info!("Search services initializing, retrying in 2 seconds...");
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-            
-            service.semantic_search(&query, limit).await.map_err(|retry_e| {
-                if retry_e.to_string().contains("Service not ready: Initializing") {
-                    "Search services are still initializing. Please try again.".to_string()
-                } else {
-                    format!("Failed to perform semantic search: {}", retry_e)
-                }
-            })?
+/// Error text the services report until their models have loaded
+const SERVICE_INITIALIZING: &str = "Service not ready: Initializing";
+
+/// Turn an error from the core services into an `AppError`. The services only report
+/// initialization as text, so this is the one place that text is inspected; any other
+/// failure becomes a `NodeOperation` error prefixed with `context`. Check
+/// `AppError::is_retryable` on the result to decide whether to try again.
+fn classify_service_error(error: &impl std::fmt::Display, context: &str) -> AppError {
+    let message = error.to_string();
+    if message.contains(SERVICE_INITIALIZING) {
+        AppError::ServiceInitializing("Please try again in a moment".to_string())
+    } else {
+        AppError::NodeOperation(format!("{}: {}", context, message))
+    }
+}
+
+/// `classify_service_error` for reads, reporting failures as `DataStore` errors
+fn classify_store_error(error: &impl std::fmt::Display, context: &str) -> AppError {
+    match classify_service_error(error, context) {
+        AppError::NodeOperation(message) => AppError::DataStore(message),
+        other => other,
+    }
+}
+
+/// Run `op`, retrying up to `config.retry_count` times, `config.retry_delay_ms` apart,
+/// while it fails because the services are still initializing. Any other error, or the
+/// last initializing error once retries run out, is returned classified, with `context`
+/// describing what failed.
+async fn with_init_retry<T, E, F, Fut>(
+    mut op: F,
+    context: &str,
+    config: &AppConfig,
+) -> Result<T, AppError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let mut attempt = 0;
+    loop {
+        let error = match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) => classify_service_error(&e, context),
+        };
+        if !error.is_retryable() || attempt >= config.retry_count {
+            return Err(error);
         }
-        Err(e) => return Err(format!("Failed to perform semantic search: {}", e)),
-    };
+
+        attempt += 1;
+        log::info!(
+            "Services initializing, retry {}/{} in {}ms...",
+            attempt,
+            config.retry_count,
+            config.retry_delay_ms
+        );
+        tokio::time::sleep(tokio::time::Duration::from_millis(config.retry_delay_ms)).await;
+    }
+}
+
+/// Search results are paged: `offset` (default 0) skips that many results before
+/// returning up to `limit`. `total_available` counts matches up to `MAX_SEARCH_WINDOW`.
+/// When `node_types` is non-empty, only nodes of those types are returned, still in
+/// relevance order.
+#[tauri::command]
+async fn semantic_search(
+    query: String,
+    limit: usize,
+    offset: Option<usize>,
+    node_types: Option<Vec<String>>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<SearchPage, AppError> {
+    let offset = offset.unwrap_or(0);
+    let _timer = log_command_timed(
+        "semantic_search",
+        &format!(
+            "query: {}, limit: {}, offset: {}, node_types: {:?}, from: {:?}, to: {:?}",
+            query, limit, offset, node_types, from_date, to_date
+        ),
+    );
+
+    if query.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search query cannot be empty".to_string(),
+        ));
+    }
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    validate_search_window(offset, limit)?;
+    let (from, to) = parse_date_range(from_date.as_deref(), to_date.as_deref())?;
+
+    let service = &state.get_service().await?;
+
+    log::info!(
+        "Performing semantic search: {} (limit: {}, offset: {})",
+        query,
+        limit,
+        offset
+    );
+
+    let search_results = with_init_retry(
+        || service.semantic_search(&query, MAX_SEARCH_WINDOW),
+        "Failed to perform semantic search",
+        &state.config().await,
+    )
+    .await?;
 
     let results: Vec<SearchResult> = search_results
         .into_iter()
         .map(|search_result| {
-            let snippet = if let Some(content_str) = search_result.node.content.as_str() {
-                let snippet_len = content_str.len().min(100);
-                format!("{}...", &content_str[..snippet_len])
-            } else {
-                "...".to_string()
-            };
-
+            let snippet = create_query_snippet(&search_result.node, &query);
             SearchResult {
                 node: search_result.node,
                 score: search_result.score as f64,
@@ -307,7 +1088,192 @@ async fn semantic_search(
         })
         .collect();
 
-    log::info!("Semantic search completed, found {} results", results.len());
+    let results = filter_by_node_types(results, node_types.as_deref());
+    let results = filter_by_created_range(results, from, to);
+    let page = paginate_results(results, offset, limit);
+    log::info!(
+        "Semantic search completed, returning {} of {} results",
+        page.results.len(),
+        page.total_available
+    );
+    Ok(page)
+}
+
+/// Semantic and keyword search combined: each result's score blends its normalized
+/// semantic and keyword scores, weighted by `semantic_weight` (0 = keyword only, 1 =
+/// semantic only). Nodes found by both searches appear once.
+#[tauri::command]
+async fn hybrid_search(
+    query: String,
+    limit: usize,
+    semantic_weight: f32,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchResult>, AppError> {
+    log_command(
+        "hybrid_search",
+        &format!(
+            "query: {}, limit: {}, semantic_weight: {}",
+            query, limit, semantic_weight
+        ),
+    );
+
+    if query.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search query cannot be empty".to_string(),
+        ));
+    }
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    if !(0.0..=1.0).contains(&semantic_weight) {
+        return Err(AppError::InvalidInput(
+            "Semantic weight must be between 0 and 1".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let semantic: Vec<SearchResult> = with_init_retry(
+        || service.semantic_search(&query, MAX_SEARCH_WINDOW),
+        "Failed to perform semantic search",
+        &state.config().await,
+    )
+    .await?
+    .into_iter()
+    .map(|search_result| {
+        let snippet = create_query_snippet(&search_result.node, &query);
+        SearchResult {
+            node: search_result.node,
+            score: search_result.score as f64,
+            snippet,
+        }
+    })
+    .collect();
+    let keyword = rank_text_matches(load_all_nodes(service).await?, &query, MAX_SEARCH_WINDOW);
+    log::info!(
+        "Hybrid search for {:?}: {} semantic and {} keyword matches",
+        query,
+        semantic.len(),
+        keyword.len()
+    );
+
+    Ok(blend_search_results(
+        semantic,
+        keyword,
+        semantic_weight as f64,
+        limit,
+    ))
+}
+
+/// Semantic search limited to `root_node_id` and its descendants. The vault-wide top
+/// `MAX_SEARCH_WINDOW` matches are filtered to the subtree, so a subtree match ranked
+/// below that window is not returned.
+#[tauri::command]
+async fn search_in_subtree(
+    root_node_id: String,
+    query: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchResult>, AppError> {
+    log_command(
+        "search_in_subtree",
+        &format!("root: {}, query: {}, limit: {}", root_node_id, query, limit),
+    );
+
+    if query.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search query cannot be empty".to_string(),
+        ));
+    }
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let subtree = hierarchy::collect_subtree(load_all_nodes(service).await?, &root_node_id);
+    if subtree.is_empty() {
+        return Err(AppError::NotFound(format!("Node {}", root_node_id)));
+    }
+    if subtree.len() > MAX_SUBTREE_SEARCH_NODES {
+        return Err(AppError::InvalidInput(format!(
+            "Subtree of {} has {} nodes, more than the {} that can be searched",
+            root_node_id,
+            subtree.len(),
+            MAX_SUBTREE_SEARCH_NODES
+        )));
+    }
+
+    let search_results = with_init_retry(
+        || service.semantic_search(&query, MAX_SEARCH_WINDOW),
+        "Failed to perform semantic search",
+        &state.config().await,
+    )
+    .await?
+    .into_iter()
+    .map(|search_result| {
+        let snippet = create_query_snippet(&search_result.node, &query);
+        SearchResult {
+            node: search_result.node,
+            score: search_result.score as f64,
+            snippet,
+        }
+    })
+    .collect();
+
+    let results = restrict_to_subtree(search_results, &subtree, limit);
+    log::info!(
+        "Subtree search in {} ({} nodes) for {:?} returned {} results",
+        root_node_id,
+        subtree.len(),
+        query,
+        results.len()
+    );
+    Ok(results)
+}
+
+/// Literal, case-insensitive substring search over node content, for exact strings such
+/// as IDs, URLs, or code that semantic search can miss. Nodes whose whole content is the
+/// query rank first; other matches rank by how early and how often the query appears.
+#[tauri::command]
+async fn text_search(
+    query: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchResult>, AppError> {
+    log_command(
+        "text_search",
+        &format!("query: {}, limit: {}", query, limit),
+    );
+
+    if query.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search query cannot be empty".to_string(),
+        ));
+    }
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let results = rank_text_matches(load_all_nodes(service).await?, &query, limit);
+
+    log::info!(
+        "Text search for {:?} returned {} results",
+        query,
+        results.len()
+    );
     Ok(results)
 }
 
@@ -315,17 +1281,12 @@ async fn semantic_search(
 async fn get_nodes_for_date(
     date_str: String,
     state: State<'_, AppState>,
-) -> Result<serde_json::Value, String> {
-    log_command("get_nodes_for_date", &format!("date: {}", date_str));
+) -> Result<serde_json::Value, AppError> {
+    let _timer = log_command_timed("get_nodes_for_date", &format!("date: {}", date_str));
 
-    let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}. Expected YYYY-MM-DD", e))?;
+    let date = parse_date(&date_str, "date format")?;
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     match service.get_hierarchical_nodes_for_date(date).await {
         Ok(hierarchical_data) => {
@@ -335,8 +1296,11 @@ async fn get_nodes_for_date(
                 hierarchical_data.children.len()
             );
 
-            serde_json::to_value(hierarchical_data)
-                .map_err(|e| format!("Failed to serialize hierarchical data: {}", e))
+            let mut payload = serde_json::to_value(hierarchical_data).map_err(|e| {
+                AppError::Serialization(format!("Failed to serialize hierarchical data: {}", e))
+            })?;
+            annotate_collapsed_state(&mut payload, &state.settings.lock().await.collapsed_nodes);
+            Ok(payload)
         }
         Err(e) => {
             log::warn!(
@@ -348,7 +1312,7 @@ async fn get_nodes_for_date(
             let nodes = service
                 .get_nodes_for_date(date)
                 .await
-                .map_err(|e| format!("Failed to get nodes for date (fallback): {}", e))?;
+                .map_err(|e| classify_store_error(&e, "Failed to get nodes for date (fallback)"))?;
 
             log::info!(
                 "Fallback retrieved {} flat nodes for date {}",
@@ -356,37 +1320,150 @@ async fn get_nodes_for_date(
                 date_str
             );
 
-            serde_json::to_value(nodes)
-                .map_err(|e| format!("Failed to serialize fallback nodes: {}", e))
+            let mut payload = serde_json::to_value(nodes).map_err(|e| {
+                AppError::Serialization(format!("Failed to serialize fallback nodes: {}", e))
+            })?;
+            annotate_collapsed_state(&mut payload, &state.settings.lock().await.collapsed_nodes);
+            Ok(payload)
         }
     }
 }
 
+/// Nodes on a date created or updated since `since_version`, for refreshing a day without
+/// re-fetching its whole hierarchy. Pass 0 to get every node, then the returned `version`
+/// on the next call.
+#[tauri::command]
+async fn get_nodes_for_date_since(
+    date_str: String,
+    since_version: u64,
+    state: State<'_, AppState>,
+) -> Result<DateDelta, AppError> {
+    log_command(
+        "get_nodes_for_date_since",
+        &format!("date: {}, since_version: {}", date_str, since_version),
+    );
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+    let nodes = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?;
+
+    let delta = date_delta(nodes, since_version);
+    log::info!(
+        "Date {} has {} nodes changed since version {} (now {})",
+        date_str,
+        delta.changed.len(),
+        since_version,
+        delta.version
+    );
+    Ok(delta)
+}
+
+/// How many nodes a date holds, not counting the date node itself
+#[tauri::command]
+async fn count_nodes_for_date(
+    date_str: String,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    log_command("count_nodes_for_date", &format!("date: {}", date_str));
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+    let nodes = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?;
+
+    Ok(content_node_count(&nodes))
+}
+
+/// Node counts for every date in the inclusive range, keyed by YYYY-MM-DD, so the
+/// calendar can mark a whole month in one call. Dates without content map to 0.
+#[tauri::command]
+async fn count_nodes_for_range(
+    from: String,
+    to: String,
+    state: State<'_, AppState>,
+) -> Result<std::collections::HashMap<String, usize>, AppError> {
+    log_command(
+        "count_nodes_for_range",
+        &format!("from: {}, to: {}", from, to),
+    );
+
+    let days = count_range_days(&from, &to)?;
+
+    let service = &state.get_service().await?;
+    let mut per_day = Vec::with_capacity(days.len());
+    for date in days {
+        let nodes = service
+            .get_nodes_for_date(date)
+            .await
+            .map_err(|e| classify_store_error(&e, &format!("Failed to get nodes for {}", date)))?;
+        per_day.push((date, nodes));
+    }
+
+    Ok(tally_date_counts(per_day))
+}
+
 #[tauri::command]
 async fn update_node_content(
     node_id: String,
     content: String,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log_command(
         "update_node_content",
         &format!("node_id: {}, content_len: {}", node_id, content.len()),
     );
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     let node_id_obj = NodeId::from_string(node_id.clone());
 
     service
         .update_node(&node_id_obj, &content)
         .await
-        .map_err(|e| format!("Failed to auto-save node content: {}", e))?;
+        .map_err(|e| classify_service_error(&e, "Failed to auto-save node content"))?;
 
     log::info!("Auto-saved content for node {} to database", node_id);
+    let date = lookup_node_date(service, &node_id).await;
+    emit_node_changed(&app, NodeChangeKind::Updated, &node_id, date);
+    Ok(())
+}
+
+/// Write only a node's metadata, leaving its content untouched. With `merge`, the given
+/// keys are applied over the stored object (a `null` value removes that key); otherwise
+/// the stored metadata is replaced wholesale.
+#[tauri::command]
+async fn update_node_metadata(
+    node_id: String,
+    metadata: serde_json::Value,
+    merge: bool,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "update_node_metadata",
+        &format!(
+            "node_id: {}, merge: {}, metadata: {}",
+            node_id, merge, metadata
+        ),
+    );
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    let updated = apply_metadata_update(node.metadata.as_ref(), metadata, merge)?;
+    store_node_metadata(&state, service, &node.id, updated).await?;
+
+    log::info!("Updated metadata for node {} (merge: {})", node_id, merge);
+    let date = lookup_node_date(service, &node_id).await;
+    emit_node_changed(&app, NodeChangeKind::Updated, &node_id, date);
     Ok(())
 }
 
@@ -403,8 +1480,9 @@ async fn update_node_structure(
     timestamp: String,
     date_str: String,
     before_sibling_id: Option<String>,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log_command(
         "update_node_structure",
         &format!(
@@ -422,11 +1500,7 @@ async fn update_node_structure(
         ),
     );
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     let node_id_obj = NodeId::from_string(node_id.clone());
 
@@ -442,6 +1516,9 @@ async fn update_node_structure(
         .as_ref()
         .map(|id| NodeId::from_string(id.clone()));
 
+    let new_parent = structure_parent_target(&operation, parent_id.as_deref());
+    ensure_no_cycle(service, &node_id, new_parent).await?;
+
     match operation.as_str() {
         "indent" => {
             let parent_node_id = parent_id.as_ref().map(|id| NodeId::from_string(id.clone()));
@@ -449,14 +1526,17 @@ async fn update_node_structure(
             service
                 .set_node_parent(&node_id_obj, parent_node_id.as_ref())
                 .await
-                .map_err(|e| format!("Failed to indent node: {}", e))?;
+                .map_err(|e| classify_service_error(&e, "Failed to indent node"))?;
 
             if before_sibling_node_id.is_some() {
                 service
                     .update_sibling_order(&node_id_obj, None, before_sibling_node_id.as_ref())
                     .await
                     .map_err(|e| {
-                        format!("Failed to update sibling order for indent operation: {}", e)
+                        classify_service_error(
+                            &e,
+                            "Failed to update sibling order for indent operation",
+                        )
                     })?;
             }
 
@@ -471,7 +1551,7 @@ async fn update_node_structure(
             service
                 .set_node_parent(&node_id_obj, None)
                 .await
-                .map_err(|e| format!("Failed to outdent node: {}", e))?;
+                .map_err(|e| classify_service_error(&e, "Failed to outdent node"))?;
 
             log::info!("Successfully outdented node {} to root level", node_id);
         }
@@ -481,14 +1561,19 @@ async fn update_node_structure(
             service
                 .set_node_parent(&node_id_obj, parent_node_id.as_ref())
                 .await
-                .map_err(|e| format!("Failed to update parent for move operation: {}", e))?;
+                .map_err(|e| {
+                    classify_service_error(&e, "Failed to update parent for move operation")
+                })?;
 
             if before_sibling_node_id.is_some() {
                 service
                     .update_sibling_order(&node_id_obj, None, before_sibling_node_id.as_ref())
                     .await
                     .map_err(|e| {
-                        format!("Failed to update sibling order for move operation: {}", e)
+                        classify_service_error(
+                            &e,
+                            "Failed to update sibling order for move operation",
+                        )
                     })?;
             }
 
@@ -505,14 +1590,19 @@ async fn update_node_structure(
             service
                 .set_node_parent(&node_id_obj, parent_node_id.as_ref())
                 .await
-                .map_err(|e| format!("Failed to set parent for child creation: {}", e))?;
+                .map_err(|e| {
+                    classify_service_error(&e, "Failed to set parent for child creation")
+                })?;
 
             if before_sibling_node_id.is_some() {
                 service
                     .update_sibling_order(&node_id_obj, None, before_sibling_node_id.as_ref())
                     .await
                     .map_err(|e| {
-                        format!("Failed to update sibling order for child creation: {}", e)
+                        classify_service_error(
+                            &e,
+                            "Failed to update sibling order for child creation",
+                        )
                     })?;
             }
 
@@ -531,21 +1621,23 @@ async fn update_node_structure(
             );
             log::warn!("Available operations: indent, outdent, move, reorder, position, create_child, add_child");
 
-            if let Some(parent_id) = parent_id.as_ref() {
-                let parent_node_id = NodeId::from_string(parent_id.clone());
-                match service
-                    .set_node_parent(&node_id_obj, Some(&parent_node_id))
-                    .await
-                {
-                    Ok(_) => {
-                        log::info!("Fallback: Updated parent relationship for unknown operation")
-                    }
-                    Err(e) => log::error!("Fallback failed: {}", e),
-                }
+            // Nothing was written, so there is no change to announce
+            let Some(parent_id) = parent_id.as_ref() else {
+                return Ok(());
+            };
+            let parent_node_id = NodeId::from_string(parent_id.clone());
+            if let Err(e) = service
+                .set_node_parent(&node_id_obj, Some(&parent_node_id))
+                .await
+            {
+                log::error!("Fallback failed: {}", e);
+                return Ok(());
             }
+            log::info!("Fallback: Updated parent relationship for unknown operation");
         }
     }
 
+    emit_node_changed(&app, NodeChangeKind::Moved, &node_id, Some(date_str));
     Ok(())
 }
 
@@ -553,18 +1645,15 @@ async fn update_node_structure(
 async fn delete_node(
     node_id: String,
     deletion_context: serde_json::Value,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log_command(
         "delete_node",
         &format!("node_id: {}, context: {}", node_id, deletion_context),
     );
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     let node_id_obj = NodeId::from_string(node_id.clone());
 
@@ -574,19 +1663,9 @@ async fn delete_node(
         deletion_context
     );
 
-    let children_ids: Vec<NodeId> = deletion_context
-        .get("childrenIds")
-        .and_then(|v| v.as_array())
-        .unwrap_or(&vec![])
-        .iter()
-        .filter_map(|v| v.as_str())
-        .map(|s| NodeId::from_string(s.to_string()))
-        .collect();
-
-    let children_transferred_to = deletion_context
-        .get("childrenTransferredTo")
-        .and_then(|v| v.as_str())
-        .map(|s| NodeId::from_string(s.to_string()));
+    let (children_ids, children_transferred_to) = parse_deletion_context(&deletion_context);
+    // Resolved up front since the node is gone afterwards
+    let date = lookup_node_date(service, &node_id).await;
 
     service
         .delete_node_with_children_transfer(
@@ -595,9 +1674,10 @@ async fn delete_node(
             children_transferred_to.as_ref(),
         )
         .await
-        .map_err(|e| format!("Failed to delete node: {}", e))?;
+        .map_err(|e| classify_service_error(&e, "Failed to delete node"))?;
 
     log::info!("Successfully deleted node {}", node_id);
+    emit_node_changed(&app, NodeChangeKind::Deleted, &node_id, date);
     Ok(())
 }
 
@@ -606,20 +1686,15 @@ async fn create_node_for_date(
     date_str: String,
     content: String,
     state: State<'_, AppState>,
-) -> Result<NodeId, String> {
+) -> Result<NodeId, AppError> {
     log_command(
         "create_node_for_date",
         &format!("date: {}, content_len: {}", date_str, content.len()),
     );
 
-    let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}. Expected YYYY-MM-DD", e))?;
+    let date = parse_date(&date_str, "date format")?;
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     log::info!(
         "Creating node for date {} with content: {}",
@@ -630,17 +1705,16 @@ async fn create_node_for_date(
     let node_id = service
         .create_node_for_date(date, &content, NodeType::Text, None)
         .await
-        .map_err(|e| format!("Failed to create node for date: {}", e))?;
+        .map_err(|e| classify_service_error(&e, "Failed to create node for date"))?;
+    flag_missing_embedding(&state, service, &node_id).await;
+    state.note_node_date(date).await;
 
-    log::info!(
-        "Created node {} for date {}",
-        node_id,
-        date_str
-    );
+    log::info!("Created node {} for date {}", node_id, date_str);
     Ok(node_id)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn create_node_for_date_with_id(
     node_id: String,
     date_str: String,
@@ -648,8 +1722,9 @@ async fn create_node_for_date_with_id(
     parent_id: Option<String>,
     node_type: Option<String>,
     before_sibling_id: Option<String>,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log_command(
         "create_node_for_date_with_id",
         &format!(
@@ -663,16 +1738,11 @@ async fn create_node_for_date_with_id(
         ),
     );
 
-    let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}. Expected YYYY-MM-DD", e))?;
+    let date = parse_date(&date_str, "date format")?;
 
     let node_id_obj = NodeId::from_string(node_id.clone());
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     log::info!(
         "Creating node with UUID {} for date {} with content: {}",
@@ -683,22 +1753,18 @@ async fn create_node_for_date_with_id(
 
     let parent_node_id = parent_id.map(NodeId::from_string);
 
-    let node_type_enum = match node_type.as_deref() {
-        Some("task") => NodeType::Task,
-        Some("image") => NodeType::Image,
-        Some("date") => NodeType::Date,
-        _ => NodeType::Text,
-    };
+    let node_type = node_type.as_deref().unwrap_or("text");
+    let node_type_enum = parse_node_type(node_type);
 
     let before_sibling_node_id = before_sibling_id.map(NodeId::from_string);
 
     let result = service
         .create_node_for_date_with_id(
-            node_id_obj,
+            node_id_obj.clone(),
             date,
             &content,
             node_type_enum,
-            None,
+            with_node_type_marker(node_type, None),
             parent_node_id,
             before_sibling_node_id,
         )
@@ -706,25 +1772,94 @@ async fn create_node_for_date_with_id(
 
     match result {
         Ok(_) => {
-            log::info!(
-                "Created node with UUID {} for date {}",
-                node_id,
-                date_str
-            );
+            flag_missing_embedding(&state, service, &node_id_obj).await;
+            state.note_node_date(date).await;
+            log::info!("Created node with UUID {} for date {}", node_id, date_str);
+            emit_node_changed(&app, NodeChangeKind::Created, &node_id, Some(date_str));
             Ok(())
         }
         Err(e) => {
-            log::error!(
-                "Failed to create node with provided ID: {}",
-                e
-            );
-            Err(format!("Failed to create node with provided ID: {}", e))
+            log::error!("Failed to create node with provided ID: {}", e);
+            Err(classify_service_error(
+                &e,
+                "Failed to create node with provided ID",
+            ))
         }
     }
 }
 
+/// Create a task on a date with the metadata the task UI relies on: `completed` starts
+/// false, and `due_date` (YYYY-MM-DD) and `priority` (low, medium or high) are set when
+/// given. Invalid values are rejected rather than stored.
+#[tauri::command]
+async fn create_task_node(
+    date_str: String,
+    content: String,
+    due_date: Option<String>,
+    priority: Option<String>,
+    parent_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<NodeId, AppError> {
+    log_command(
+        "create_task_node",
+        &format!(
+            "date: {}, content_len: {}, due_date: {:?}, priority: {:?}, parent_id: {:?}",
+            date_str,
+            content.len(),
+            due_date,
+            priority,
+            parent_id
+        ),
+    );
+
+    let date = parse_date(&date_str, "date format")?;
+    let metadata = task_metadata(due_date.as_deref(), priority.as_deref())?;
+
+    let service = &state.get_service().await?;
+
+    let node_id = NodeId::new();
+    service
+        .create_node_for_date_with_id(
+            node_id.clone(),
+            date,
+            &content,
+            NodeType::Task,
+            Some(metadata),
+            parent_id.map(NodeId::from_string),
+            None,
+        )
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to create task node"))?;
+    flag_missing_embedding(&state, service, &node_id).await;
+    state.note_node_date(date).await;
+
+    log::info!("Created task {} for date {}", node_id, date_str);
+    emit_node_changed(&app, NodeChangeKind::Created, &node_id.0, Some(date_str));
+    Ok(node_id)
+}
+
+#[tauri::command]
+async fn get_api_info() -> Result<api::ApiInfo, AppError> {
+    log_command("get_api_info", "");
+    Ok(api::api_info())
+}
+
+/// Compare the commands declared in `api::COMMANDS` with those actually registered with the
+/// invoke handler. An empty list means the two agree.
+#[tauri::command]
+async fn verify_command_registry() -> Result<Vec<String>, AppError> {
+    log_command("verify_command_registry", "");
+
+    let mismatches = api::registry_mismatches(REGISTERED_COMMANDS);
+    for mismatch in &mismatches {
+        log::warn!("Command registry mismatch: {}", mismatch);
+    }
+    Ok(mismatches)
+}
+
 #[tauri::command]
-async fn get_today_date() -> Result<String, String> {
+async fn get_today_date() -> Result<String, AppError> {
     let today = chrono::Utc::now().date_naive();
     Ok(today.format("%Y-%m-%d").to_string())
 }
@@ -739,8 +1874,9 @@ async fn upsert_node(
     before_sibling_id: Option<String>,
     node_type: String,
     metadata: Option<serde_json::Value>,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<UpsertOutcome, AppError> {
     log_command(
         "upsert_node",
         &format!(
@@ -757,35 +1893,81 @@ async fn upsert_node(
 
     log::info!("Processing unified upsert for node {}", node_id);
 
-    let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid date format: {}. Expected YYYY-MM-DD", e))?;
+    let date = parse_date(&date_str, "date format")?;
+
+    if let Some(metadata) = metadata.as_ref() {
+        check_metadata_size(metadata, state.config().await.max_metadata_bytes)?;
+    }
 
     let node_id_obj = NodeId::from_string(node_id.clone());
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
-    let node_type_enum = match node_type.as_str() {
-        "task" => NodeType::Task,
-        "image" => NodeType::Image,
-        "date" => NodeType::Date,
-        "ai-chat" => NodeType::Text,
-        _ => NodeType::Text,
-    };
+    let node_type_enum = parse_node_type(&node_type);
+    let metadata = with_node_type_marker(&node_type, metadata);
 
     let parent_node_id = parent_id.map(NodeId::from_string);
     let before_sibling_node_id = before_sibling_id.map(NodeId::from_string);
 
-    if node_type == "ai-chat" && metadata.is_some() {
+    if node_type == AI_CHAT_NODE_TYPE && metadata.is_some() {
         log::info!("Processing AIChatNode with metadata");
     }
 
-    match service
-        .create_node_for_date_with_id(
-            node_id_obj,
+    let existing = service
+        .get_node(&node_id_obj)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to load node"))?;
+    if let Some(existing) = existing {
+        let changes = upsert_changes(
+            &existing,
+            &content,
+            metadata.as_ref(),
+            parent_node_id.as_ref(),
+            before_sibling_node_id.as_ref(),
+        );
+        if changes.parent {
+            let new_parent = parent_node_id.as_ref().map(|id| id.0.as_str());
+            ensure_no_cycle(service, &node_id, new_parent).await?;
+        }
+        if changes.content {
+            service
+                .update_node(&node_id_obj, &content)
+                .await
+                .map_err(|e| classify_service_error(&e, "Failed to update node content"))?;
+        }
+        if let Some(metadata) = metadata.filter(|_| changes.metadata) {
+            store_node_metadata(&state, service, &node_id_obj, metadata).await?;
+        }
+        if changes.content {
+            // After the metadata write, so the flag isn't overwritten by it
+            flag_missing_embedding(&state, service, &node_id_obj).await;
+        }
+        if changes.parent {
+            service
+                .set_node_parent(&node_id_obj, parent_node_id.as_ref())
+                .await
+                .map_err(|e| classify_service_error(&e, "Failed to update node parent"))?;
+        }
+        if changes.sibling_order {
+            service
+                .update_sibling_order(&node_id_obj, None, before_sibling_node_id.as_ref())
+                .await
+                .map_err(|e| classify_service_error(&e, "Failed to update sibling order"))?;
+        }
+
+        log::info!("Unified upsert updated node {}: {:?}", node_id, changes);
+        let kind = if changes.parent || changes.sibling_order {
+            NodeChangeKind::Moved
+        } else {
+            NodeChangeKind::Updated
+        };
+        emit_node_changed(&app, kind, &node_id, Some(date_str));
+        return Ok(UpsertOutcome::Updated);
+    }
+
+    match service
+        .create_node_for_date_with_id(
+            node_id_obj.clone(),
             date,
             &content,
             node_type_enum,
@@ -796,45 +1978,123 @@ async fn upsert_node(
         .await
     {
         Ok(_) => {
-            log::info!("Unified upsert completed successfully");
-            Ok(())
+            flag_missing_embedding(&state, service, &node_id_obj).await;
+            state.note_node_date(date).await;
+            log::info!("Unified upsert inserted node {}", node_id);
+            emit_node_changed(&app, NodeChangeKind::Created, &node_id, Some(date_str));
+            Ok(UpsertOutcome::Inserted)
         }
         Err(e) => {
             log::error!("Unified upsert failed: {}", e);
-            Err(format!("Failed to upsert node: {}", e))
+            Err(classify_service_error(&e, "Failed to upsert node"))
         }
     }
 }
 
 #[tauri::command]
-async fn create_image_node(_state: State<'_, AppState>) -> Result<ImageData, String> {
+async fn create_image_node(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImageData, AppError> {
     log_command("create_image_node", "opening file dialog");
 
-    Err("File dialog not yet implemented - waiting for Tauri API update".to_string())
+    // The picker blocks until the user chooses, so keep it off the async runtime
+    let picked = tokio::task::spawn_blocking(move || {
+        app.dialog()
+            .file()
+            .add_filter("Images", IMAGE_DIALOG_EXTENSIONS)
+            .blocking_pick_file()
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("File dialog failed: {}", e)))?;
+
+    let Some(picked) = picked else {
+        log::info!("Image selection cancelled");
+        return Err(AppError::InvalidInput(NO_FILE_SELECTED.to_string()));
+    };
+    let file_path = picked
+        .into_path()
+        .map_err(|e| AppError::InvalidInput(format!("Selected file is not a local path: {}", e)))?;
+
+    // The user chose this file themselves, so it is allowed wherever it lives
+    let roots = [file_path.clone()];
+    process_image_file(file_path.to_string_lossy().into_owned(), &roots, &state).await
 }
 
 #[tauri::command]
 async fn process_dropped_files(
     file_paths: Vec<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<ImageData>, String> {
+) -> Result<DropResult, AppError> {
     log_command(
         "process_dropped_files",
         &format!("processing {} files", file_paths.len()),
     );
 
-    let mut results = Vec::new();
+    // Dropped paths arrive from the webview, so only the usual roots are readable
+    let roots = allowed_file_roots(&state.config().await);
+    let result =
+        process_each_dropped(file_paths, |path| process_image_file(path, &roots, &state)).await;
+
+    log::info!(
+        "Processed dropped files: {} imported, {} failed",
+        result.succeeded.len(),
+        result.failed.len()
+    );
+    Ok(result)
+}
 
+/// Run `process` on every dropped image, recording failures and non-image files instead
+/// of skipping them
+async fn process_each_dropped<F, Fut, E>(file_paths: Vec<String>, mut process: F) -> DropResult
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<ImageData, E>>,
+    E: std::fmt::Display,
+{
+    let mut result = DropResult::default();
     for file_path in file_paths {
-        if is_image_file(&file_path) {
-            match process_image_file(file_path, &state).await {
-                Ok(image_data) => results.push(image_data),
-                Err(e) => log::warn!("Failed to process image file: {}", e),
+        if !is_image_file(&file_path) {
+            result.failed.push(FileError {
+                path: file_path,
+                reason: UNSUPPORTED_FILE_TYPE.to_string(),
+            });
+            continue;
+        }
+        match process(file_path.clone()).await {
+            Ok(image_data) => result.succeeded.push(image_data),
+            Err(e) => {
+                log::warn!("Failed to process image file {}: {}", file_path, e);
+                result.failed.push(FileError {
+                    path: file_path,
+                    reason: e.to_string(),
+                });
             }
         }
     }
+    result
+}
 
-    Ok(results)
+/// The full-resolution original of an image as a data URL, for when the user opens it.
+/// Lists should use the `thumbnail_url` from `ImageData` instead.
+#[tauri::command]
+async fn get_full_image(file_path: String, state: State<'_, AppState>) -> Result<String, AppError> {
+    log_command("get_full_image", &format!("path: {}", file_path));
+
+    let config = state.config().await;
+    let roots = allowed_file_roots(&config);
+    let path = file_path.clone();
+    let data_url =
+        tokio::task::spawn_blocking(move || read_full_image(&path, &roots, config.max_image_bytes))
+            .await
+            .map_err(|e| AppError::Internal(format!("Image read task failed: {}", e)))??;
+
+    log::info!(
+        "Loaded full image {} ({} bytes as data URL)",
+        file_path,
+        data_url.len()
+    );
+    Ok(data_url)
 }
 
 #[tauri::command]
@@ -842,8 +2102,8 @@ async fn multimodal_search(
     query: String,
     config: MultimodalSearchConfig,
     state: State<'_, AppState>,
-) -> Result<Vec<SearchResult>, String> {
-    log_command(
+) -> Result<Vec<SearchResult>, AppError> {
+    let _timer = log_command_timed(
         "multimodal_search",
         &format!(
             "query: {}, include_images: {}",
@@ -852,25 +2112,23 @@ async fn multimodal_search(
     );
 
     if query.trim().is_empty() {
-        return Err("Search query cannot be empty".to_string());
+        return Err(AppError::InvalidInput(
+            "Search query cannot be empty".to_string(),
+        ));
     }
 
-    let mut service_guard = state.nodespace_service.lock().await;
-    if service_guard.is_none() {
-        *service_guard = Some(initialize_nodespace_service().await?);
-    }
-    let service = service_guard.as_ref().unwrap();
+    let service = &state.get_service().await?;
 
     let search_results = service
         .semantic_search(&query, config.max_results)
         .await
-        .map_err(|e| format!("Failed to perform multimodal search: {}", e))?;
+        .map_err(|e| classify_service_error(&e, "Failed to perform multimodal search"))?;
 
     let results: Vec<SearchResult> = search_results
         .into_iter()
         .filter(|result| result.score >= config.min_similarity_threshold)
         .map(|search_result| {
-            let snippet = create_search_snippet(&search_result.node);
+            let snippet = create_query_snippet(&search_result.node, &query);
             SearchResult {
                 node: search_result.node,
                 score: search_result.score as f64,
@@ -886,158 +2144,5110 @@ async fn multimodal_search(
     Ok(results)
 }
 
-async fn process_image_file(
-    file_path: String,
-    _state: &State<'_, AppState>,
-) -> Result<ImageData, String> {
-    use std::fs;
-
-    if !is_image_file(&file_path) {
-        return Err("File is not a supported image format".to_string());
-    }
+#[tauri::command]
+async fn rename_image_file(
+    node_id: String,
+    new_filename: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "rename_image_file",
+        &format!("node_id: {}, new_filename: {}", node_id, new_filename),
+    );
 
-    let metadata =
-        fs::metadata(&file_path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+    let service = &state.get_service().await?;
 
-    if metadata.len() > 10 * 1024 * 1024 {
-        return Err("Image file too large (max 10MB)".to_string());
-    }
+    let node = load_node(service, &node_id).await?;
+    let mut metadata = node
+        .metadata
+        .clone()
+        .unwrap_or_else(|| serde_json::json!({}));
 
-    let image_data =
-        fs::read(&file_path).map_err(|e| format!("Failed to read image file: {}", e))?;
+    let current_path = metadata
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("Node {} has no stored image file", node_id))
+        })?;
 
-    let img =
-        image::load_from_memory(&image_data).map_err(|e| format!("Invalid image format: {}", e))?;
+    let target_path = validate_image_rename(&current_path, &new_filename)?;
+    if target_path.exists() {
+        return Err(AppError::InvalidInput(format!(
+            "A file named '{}' already exists",
+            target_path.display()
+        )));
+    }
 
-    let (width, height) = (img.width(), img.height());
+    std::fs::rename(&current_path, &target_path)
+        .map_err(|e| AppError::Internal(format!("Failed to rename image file: {}", e)))?;
 
-    let filename = std::path::Path::new(&file_path)
+    let final_filename = target_path
         .file_name()
         .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
+        .unwrap_or_default()
         .to_string();
 
-    let mime_type = mime_guess::from_path(&file_path)
-        .first_or_octet_stream()
-        .to_string();
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            "file_path".to_string(),
+            serde_json::Value::String(target_path.to_string_lossy().to_string()),
+        );
+        object.insert(
+            "filename".to_string(),
+            serde_json::Value::String(final_filename),
+        );
+    }
 
-    let embeddings = vec![0.0; 384];
+    if let Err(e) = store_node_metadata(&state, service, &node.id, metadata).await {
+        // Keep disk and database consistent: undo the rename if the metadata write fails
+        let _ = std::fs::rename(&target_path, &current_path);
+        return Err(e);
+    }
 
-    use base64::{engine::general_purpose, Engine as _};
-    let base64_data = general_purpose::STANDARD.encode(&image_data);
-    let blob_url = format!("data:{};base64,{}", mime_type, base64_data);
+    log::info!(
+        "Renamed image for node {} to {}",
+        node_id,
+        target_path.display()
+    );
+    Ok(())
+}
 
-    let image_metadata = ImageMetadata {
-        filename,
-        mime_type,
-        file_size: metadata.len(),
-        width,
-        height,
-        exif_data: None,
-        ai_description: None,
-        created_at: chrono::Utc::now(),
-    };
+#[tauri::command]
+async fn get_nodes_by_time_of_day(
+    start_hour: u32,
+    end_hour: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, AppError> {
+    log_command(
+        "get_nodes_by_time_of_day",
+        &format!("start_hour: {}, end_hour: {}", start_hour, end_hour),
+    );
 
-    let image_data = ImageData {
-        file_path,
-        metadata: image_metadata,
-        embeddings,
-        blob_url,
-        dimensions: (width, height),
-    };
+    if start_hour > 23 || end_hour > 23 {
+        return Err(AppError::InvalidInput(
+            "Hours must be between 0 and 23".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let nodes = load_all_nodes(service).await?;
+    let matching = filter_nodes_by_time_of_day(nodes, start_hour, end_hour);
 
     log::info!(
-        "Processed image file: {} ({}x{})",
-        image_data.metadata.filename,
-        width,
-        height
+        "Found {} nodes created between {}:00 and {}:59",
+        matching.len(),
+        start_hour,
+        end_hour
     );
-    Ok(image_data)
+    Ok(matching)
 }
 
-fn is_image_file(file_path: &str) -> bool {
-    let path = std::path::Path::new(file_path);
-    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
-        matches!(
-            extension.to_lowercase().as_str(),
-            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp"
-        )
-    } else {
-        false
+#[tauri::command]
+async fn validate_node_metadata(
+    node_id: String,
+    schema: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<ValidationResult, AppError> {
+    log_command("validate_node_metadata", &format!("node_id: {}", node_id));
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    let result = validate_metadata_against_schema(node.metadata.as_ref(), &schema)?;
+
+    log::info!(
+        "Validated metadata for node {}: {} missing, {} invalid",
+        node_id,
+        result.missing.len(),
+        result.invalid.len()
+    );
+    Ok(result)
+}
+
+#[tauri::command]
+async fn autolink_mentions(node_id: String, state: State<'_, AppState>) -> Result<usize, AppError> {
+    log_command("autolink_mentions", &format!("node_id: {}", node_id));
+
+    let service = &state.get_service().await?;
+
+    let target = load_node(service, &node_id).await?;
+    let title = node_title(&target)
+        .ok_or_else(|| AppError::InvalidInput(format!("Node {} has no title to link", node_id)))?;
+
+    let mut created = 0;
+    for node in load_all_nodes(service).await? {
+        if node.id.0 == target.id.0 {
+            continue;
+        }
+        let Some(content) = node.content.as_str() else {
+            continue;
+        };
+        if !contains_whole_phrase(content, &title) {
+            continue;
+        }
+
+        if let Some(updated) = add_reference(node.metadata.as_ref(), &target.id.0) {
+            store_node_metadata(&state, service, &node.id, updated).await?;
+            created += 1;
+        }
     }
+
+    log::info!(
+        "Created {} mention links to node {} ('{}')",
+        created,
+        node_id,
+        title
+    );
+    Ok(created)
 }
 
-fn create_search_snippet(node: &Node) -> String {
-    if let Some(content_str) = node.content.as_str() {
-        let snippet_len = content_str.len().min(100);
-        if content_str.len() > snippet_len {
-            format!("{}...", &content_str[..snippet_len])
-        } else {
-            content_str.to_string()
+/// The earliest and latest days holding at least one node, as YYYY-MM-DD, so the date
+/// picker can limit navigation. The store has no min/max date query, so the bounds come
+/// from one full scan and are cached; creates widen the cache, and each call re-checks
+/// the two boundary days with per-date queries, rescanning only when one has emptied.
+#[tauri::command]
+async fn get_date_bounds(
+    state: State<'_, AppState>,
+) -> Result<(Option<String>, Option<String>), AppError> {
+    log_command("get_date_bounds", "");
+
+    let service = &state.get_service().await?;
+
+    let mut cached = state.date_bounds.lock().await;
+    let still_valid = match *cached {
+        Some((earliest, latest)) => {
+            date_has_content(service, earliest).await? && date_has_content(service, latest).await?
         }
-    } else if let Some(metadata) = node.metadata.as_ref().and_then(|m| m.as_object()) {
-        if let Some(node_type) = metadata.get("node_type").and_then(|v| v.as_str()) {
-            match node_type {
-                "image" => {
-                    let filename = metadata
-                        .get("filename")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("image");
-                    format!("Image: {}", filename)
-                }
-                _ => "...".to_string(),
+        None => false,
+    };
+    if !still_valid {
+        *cached = Some(compute_date_bounds(&load_all_nodes(service).await?));
+    }
+    let (earliest, latest) = cached.unwrap_or_default();
+
+    log::info!("Date bounds: {:?} to {:?}", earliest, latest);
+    Ok((
+        earliest.map(|d| d.format("%Y-%m-%d").to_string()),
+        latest.map(|d| d.format("%Y-%m-%d").to_string()),
+    ))
+}
+
+#[tauri::command]
+async fn retry_failed_embeddings(
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<RetrySummary, AppError> {
+    log_command("retry_failed_embeddings", "");
+
+    let service = &state.get_service().await?;
+
+    let flagged: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(needs_embedding_retry)
+        .collect();
+
+    let total = flagged.len();
+    let mut summary = RetrySummary {
+        attempted: total,
+        succeeded: 0,
+        failed: Vec::new(),
+    };
+
+    for (index, node) in flagged.into_iter().enumerate() {
+        let outcome = match service.regenerate_embedding(&node.id).await {
+            Ok(_) => {
+                let metadata = clear_embedding_failure(node.metadata.as_ref());
+                store_node_metadata(&state, service, &node.id, metadata)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        match outcome {
+            Ok(_) => summary.succeeded += 1,
+            Err(e) => {
+                log::warn!("Embedding retry failed for node {}: {}", node.id, e);
+                summary.failed.push(node.id.0.clone());
             }
-        } else {
-            "...".to_string()
         }
-    } else {
-        "...".to_string()
+
+        let _ = app.emit(
+            "embedding-retry-progress",
+            ProgressEvent {
+                processed: index + 1,
+                total,
+                node_id: Some(node.id.0.clone()),
+            },
+        );
     }
+
+    log::info!(
+        "Embedding retry finished: {}/{} succeeded",
+        summary.succeeded,
+        summary.attempted
+    );
+    Ok(summary)
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    if let Err(e) = init_logging() {
-        eprintln!("Failed to initialize logging: {}", e);
+#[tauri::command]
+async fn render_date_html(
+    date_str: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    log_command("render_date_html", &format!("date: {}", date_str));
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+
+    let nodes = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?;
+
+    // Image paths come from node metadata, so they get the same checks as get_full_image
+    let config = state.config().await;
+    let roots = allowed_file_roots(&config);
+    let title = date.format("%A, %B %-d, %Y").to_string();
+    let outline = hierarchy::build_outline(nodes);
+    let top_level = outline.len();
+    let html = tokio::task::spawn_blocking(move || {
+        let inline_image = |path: &str| read_full_image(path, &roots, config.max_image_bytes).ok();
+        render::render_html_document(&title, &outline, &inline_image)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("HTML render task failed: {}", e)))?;
+
+    log::info!(
+        "Rendered {} top-level nodes for date {} as HTML ({} bytes)",
+        top_level,
+        date_str,
+        html.len()
+    );
+    Ok(html)
+}
+
+/// A day's outline as nested Markdown bullets, for pasting into other tools. Days
+/// without nodes export as an empty string.
+#[tauri::command]
+async fn export_date_as_markdown(
+    date_str: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    log_command("export_date_as_markdown", &format!("date: {}", date_str));
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+
+    let nodes = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?;
+
+    let markdown = render::render_markdown_outline(&hierarchy::build_outline(nodes));
+
+    log::info!(
+        "Exported date {} as Markdown ({} bytes)",
+        date_str,
+        markdown.len()
+    );
+    Ok(markdown)
+}
+
+#[tauri::command]
+async fn replace_in_subtree(
+    root_node_id: String,
+    find: String,
+    replace: String,
+    dry_run: bool,
+    state: State<'_, AppState>,
+) -> Result<BulkReplaceResult, AppError> {
+    log_command(
+        "replace_in_subtree",
+        &format!(
+            "root: {}, find_len: {}, replace_len: {}, dry_run: {}",
+            root_node_id,
+            find.len(),
+            replace.len(),
+            dry_run
+        ),
+    );
+
+    if find.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search text cannot be empty".to_string(),
+        ));
     }
 
-    log_startup();
+    let service = &state.get_service().await?;
 
-    tauri::Builder::default()
-        .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_clipboard_manager::init())
-        .manage(AppState::default())
-        .setup(|_app| {
-            log_service_init("Application State");
-            log_service_ready("Application State");
+    let subtree = hierarchy::collect_subtree(load_all_nodes(service).await?, &root_node_id);
+    if subtree.is_empty() {
+        return Err(AppError::NotFound(format!("Node {}", root_node_id)));
+    }
 
-            log::info!("NodeSpace Desktop initialized");
-            Ok(())
-        })
-        .on_window_event(|_window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                log_shutdown();
-            }
-        })
-        .invoke_handler(tauri::generate_handler![
-            greet,
-            create_knowledge_node,
-            update_node,
-            process_query,
-            semantic_search,
-            get_nodes_for_date,
-            update_node_content,
-            update_node_structure,
-            delete_node,
-            create_node_for_date,
-            create_node_for_date_with_id,
-            get_today_date,
-            upsert_node,
-            create_image_node,
-            process_dropped_files,
-            multimodal_search
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file
+    let planned = plan_replacements(&subtree, &find, &replace);
+    let result = BulkReplaceResult {
+        affected_node_ids: planned.iter().map(|p| p.node_id.0.clone()).collect(),
+        replacements: planned.iter().map(|p| p.occurrences).sum(),
+        dry_run,
+    };
+
+    if !dry_run {
+        for replacement in &planned {
+            // update_node re-embeds the new content
+            service
+                .update_node(&replacement.node_id, &replacement.new_content)
+                .await
+                .map_err(|e| {
+                    classify_service_error(
+                        &e,
+                        &format!("Failed to update node {}", replacement.node_id),
+                    )
+                })?;
+        }
+    }
+
+    log::info!(
+        "Replace in subtree {}: {} occurrences across {} nodes (dry_run: {})",
+        root_node_id,
+        result.replacements,
+        result.affected_node_ids.len(),
+        dry_run
+    );
+    Ok(result)
+}
+
+/// Report ordering conflicts among the children of `parent_id` (root-level nodes when
+/// `None`). Detected conflicts can be repaired by re-positioning the affected nodes with
+/// `update_node_structure`.
+#[tauri::command]
+async fn detect_sibling_conflicts(
+    parent_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<hierarchy::SiblingConflict>, AppError> {
+    log_command(
+        "detect_sibling_conflicts",
+        &format!("parent_id: {:?}", parent_id),
+    );
+
+    let service = &state.get_service().await?;
+
+    let siblings: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(|n| n.parent_id.as_ref().map(|p| &p.0) == parent_id.as_ref())
+        .filter(|n| parent_id.is_some() || n.r#type != "date")
+        .collect();
+
+    let conflicts = hierarchy::find_sibling_conflicts(&siblings);
+    if !conflicts.is_empty() {
+        log::warn!(
+            "Found {} sibling ordering conflicts under parent {:?}",
+            conflicts.len(),
+            parent_id
+        );
+    }
+    Ok(conflicts)
+}
+
+#[tauri::command]
+async fn backfill_thumbnails(
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<BackfillSummary, AppError> {
+    log_command("backfill_thumbnails", "");
+
+    let service = &state.get_service().await?;
+
+    let candidates: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(needs_thumbnail)
+        .collect();
+
+    let total = candidates.len();
+    let mut summary = BackfillSummary {
+        total,
+        generated: 0,
+        skipped_missing: 0,
+        failed: Vec::new(),
+    };
+
+    for (index, node) in candidates.into_iter().enumerate() {
+        match existing_image_path(&node) {
+            None => summary.skipped_missing += 1,
+            Some(path) => {
+                let outcome = match std::fs::read(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| create_thumbnail_data_url(&bytes).map_err(String::from))
+                {
+                    Ok(thumbnail_url) => {
+                        let mut metadata = node.metadata.clone().unwrap_or_default();
+                        if let Some(object) = metadata.as_object_mut() {
+                            object.insert(
+                                "thumbnail_url".to_string(),
+                                serde_json::Value::String(thumbnail_url),
+                            );
+                        }
+                        store_node_metadata(&state, service, &node.id, metadata)
+                            .await
+                            .map_err(|e| e.to_string())
+                    }
+                    Err(e) => Err(e),
+                };
+
+                match outcome {
+                    Ok(_) => summary.generated += 1,
+                    Err(e) => {
+                        log::warn!("Thumbnail backfill failed for node {}: {}", node.id, e);
+                        summary.failed.push(node.id.0.clone());
+                    }
+                }
+            }
+        }
+
+        let _ = app.emit(
+            "thumbnail-backfill-progress",
+            ProgressEvent {
+                processed: index + 1,
+                total,
+                node_id: Some(node.id.0.clone()),
+            },
+        );
+    }
+
+    log::info!(
+        "Thumbnail backfill finished: {} generated, {} missing sources, {} failed",
+        summary.generated,
+        summary.skipped_missing,
+        summary.failed.len()
+    );
+    Ok(summary)
+}
+
+#[tauri::command]
+async fn cluster_nodes(k: usize, state: State<'_, AppState>) -> Result<Vec<Cluster>, AppError> {
+    log_command("cluster_nodes", &format!("k: {}", k));
+
+    let service = &state.get_service().await?;
+
+    let nodes: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(|n| n.r#type != "date")
+        .collect();
+    let embedded = load_embeddings(service, nodes).await?;
+
+    if k == 0 || k > embedded.len() {
+        return Err(AppError::InvalidInput(format!(
+            "k must be between 1 and the number of embedded nodes ({})",
+            embedded.len()
+        )));
+    }
+
+    let (nodes, vectors): (Vec<Node>, Vec<Vec<f32>>) = embedded.into_iter().unzip();
+    let clusters = build_clusters(&nodes, &vectors, embeddings::seed_centroids(&vectors, k));
+
+    log::info!(
+        "Clustered {} nodes into {} clusters",
+        nodes.len(),
+        clusters.len()
+    );
+    Ok(clusters)
+}
+
+/// Deprecated alias of `set_active_model`, kept registered for older frontends.
+#[tauri::command]
+async fn set_default_model(model: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    log_command("set_default_model", &format!("model: {}", model));
+
+    set_active_model(model, state).await
+}
+
+/// Installed generation models with their size on disk. The active model is the saved
+/// default, or the first installed model when none has been chosen.
+#[tauri::command]
+async fn list_available_models(state: State<'_, AppState>) -> Result<Vec<ModelInfo>, AppError> {
+    log_command("list_available_models", "");
+
+    let models_dir = state.config().await.models_path;
+    let active = resolve_model(None, &*state.settings.lock().await)
+        .or_else(|| available_models(&models_dir).into_iter().next());
+    let models = installed_models(&models_dir, active.as_deref());
+
+    log::info!("Found {} installed models", models.len());
+    Ok(models)
+}
+
+/// Switch the model used for generation. The choice is saved, so it survives restarts.
+#[tauri::command]
+async fn set_active_model(model_name: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    log_command("set_active_model", &format!("model_name: {}", model_name));
+
+    activate_model(&state, &model_name).await?;
+    log::info!("Active model set to {}", model_name);
+    Ok(())
+}
+
+/// Validate `model` against the installed models and persist it as the default
+async fn activate_model(state: &AppState, model: &str) -> Result<(), AppError> {
+    validate_model_name(model, &available_models(&state.config().await.models_path))?;
+
+    let mut settings = state.settings.lock().await;
+    let mut updated = settings.clone();
+    updated.default_model = Some(model.to_string());
+    updated.save()?;
+    *settings = updated;
+    Ok(())
+}
+
+/// Absolute path of `nodespace.log`, for showing users where to find it
+#[tauri::command]
+async fn get_log_file_path() -> Result<String, AppError> {
+    log_command("get_log_file_path", "");
+
+    let logs_dir = ensure_logs_directory()
+        .map_err(|e| AppError::Internal(format!("Failed to locate logs directory: {}", e)))?;
+    Ok(logs_dir.join(LOG_FILE_NAME).to_string_lossy().into_owned())
+}
+
+/// Open the logs directory in the system file manager, so users can attach logs to bug
+/// reports
+#[tauri::command]
+async fn open_logs_directory(app: AppHandle) -> Result<(), AppError> {
+    log_command("open_logs_directory", "");
+
+    let logs_dir = ensure_logs_directory()
+        .map_err(|e| AppError::Internal(format!("Failed to locate logs directory: {}", e)))?;
+    app.opener()
+        .open_path(logs_dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError::Internal(format!("Failed to open logs directory: {}", e)))?;
+
+    log::info!("Opened logs directory {}", logs_dir.display());
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_model_limits(state: State<'_, AppState>) -> Result<ModelLimits, AppError> {
+    log_command("get_model_limits", "");
+
+    let settings = state.settings.lock().await;
+    let model = resolve_model(None, &settings).or_else(|| {
+        available_models(&state.config().await.models_path)
+            .into_iter()
+            .next()
+    });
+
+    Ok(model_limits_for(model.as_deref()))
+}
+
+/// Persist whether a node is collapsed in the outline. This is view state, so it is kept
+/// in the workspace settings and the node itself, including its `updated_at`, is never
+/// written.
+#[tauri::command]
+async fn set_node_collapsed(
+    node_id: String,
+    collapsed: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "set_node_collapsed",
+        &format!("node_id: {}, collapsed: {}", node_id, collapsed),
+    );
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+
+    let mut settings = state.settings.lock().await;
+    let mut updated = settings.clone();
+    updated.collapsed_nodes.insert(node.id.0, collapsed);
+    updated.save()?;
+    *settings = updated;
+
+    log::info!("Set collapsed={} for node {}", collapsed, node_id);
+    Ok(())
+}
+
+/// Add `tags` to the top `limit` semantic matches for `query`, returning how many nodes
+/// gained a tag. With `dry_run` nothing is written and the result also previews the IDs
+/// of the nodes that would be tagged.
+#[tauri::command]
+async fn tag_search_results(
+    query: String,
+    tags: Vec<String>,
+    limit: usize,
+    dry_run: bool,
+    state: State<'_, AppState>,
+) -> Result<TagSearchResult, AppError> {
+    log_command(
+        "tag_search_results",
+        &format!(
+            "query: {}, tags: {:?}, limit: {}, dry_run: {}",
+            query, tags, limit, dry_run
+        ),
+    );
+
+    if query.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search query cannot be empty".to_string(),
+        ));
+    }
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let tags = normalize_tags(&tags);
+    if tags.is_empty() {
+        return Err(AppError::InvalidInput(
+            "At least one tag is required".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let search_results = service
+        .semantic_search(&query, limit)
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to perform semantic search"))?;
+
+    let updates = plan_tag_updates(search_results.into_iter().map(|r| r.node), &tags);
+    let result = tag_search_result(&updates, dry_run);
+
+    if dry_run {
+        log::info!(
+            "Dry run: would tag {} nodes: {:?}",
+            result.count,
+            result.preview
+        );
+        return Ok(result);
+    }
+
+    for (node_id, metadata) in updates {
+        store_node_metadata(&state, service, &node_id, metadata).await?;
+    }
+
+    log::info!("Tagged {} nodes matching '{}'", result.count, query);
+    Ok(result)
+}
+
+/// Report nodes whose stored `date` metadata disagrees with the date node referenced by
+/// their `root_id`, optionally rewriting the stored date to match the root.
+#[tauri::command]
+async fn verify_node_dates(
+    repair: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<DateMismatch>, AppError> {
+    log_command("verify_node_dates", &format!("repair: {}", repair));
+
+    let service = &state.get_service().await?;
+
+    let nodes = load_all_nodes(service).await?;
+    let mut mismatches = find_date_mismatches(&nodes);
+
+    if repair {
+        let by_id: HashMap<&str, &Node> = nodes.iter().map(|n| (n.id.0.as_str(), n)).collect();
+        for mismatch in mismatches.iter_mut() {
+            let Some(node) = by_id.get(mismatch.node_id.as_str()) else {
+                continue;
+            };
+            let metadata = with_date_context(node.metadata.as_ref(), &mismatch.root_date);
+            match store_node_metadata(&state, service, &node.id, metadata).await {
+                Ok(_) => mismatch.repaired = true,
+                Err(e) => log::warn!("Failed to repair date for node {}: {}", node.id, e),
+            }
+        }
+    }
+
+    log::info!(
+        "Date verification found {} mismatches ({} repaired)",
+        mismatches.len(),
+        mismatches.iter().filter(|m| m.repaired).count()
+    );
+    Ok(mismatches)
+}
+
+/// Generate text straight from the language model, without retrieving any notes
+#[tauri::command]
+async fn generate_text(
+    prompt: String,
+    model: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    log_command(
+        "generate_text",
+        &format!("prompt_len: {}, model: {:?}", prompt.len(), model),
+    );
+
+    let model = resolve_model(model, &*state.settings.lock().await);
+    validate_generation_request(
+        &prompt,
+        model.as_deref(),
+        &available_models(&state.config().await.models_path),
+    )?;
+
+    let service = &state.get_service().await?;
+
+    let text = service
+        .generate_text(&prompt, model.as_deref())
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to generate text"))?;
+
+    log::info!("Generated {} characters without retrieval", text.len());
+    Ok(text)
+}
+
+#[tauri::command]
+async fn get_acceleration_info(state: State<'_, AppState>) -> Result<AccelerationInfo, AppError> {
+    log_command("get_acceleration_info", "");
+
+    let service = &state.get_service().await?;
+
+    let reported = match service.compute_backend().await {
+        Ok(backend) => backend,
+        Err(e) => {
+            log::warn!("Could not query NLP compute backend: {}", e);
+            None
+        }
+    };
+
+    let info = acceleration_from_backend(reported.as_deref());
+    log::info!("NLP acceleration: {:?}", info);
+    Ok(info)
+}
+
+/// Append `contents` as children of `parent_id`, in order, after any existing children.
+/// All contents are checked and embedded before anything is written, and if a write still
+/// fails the children already created are removed again, so the batch lands whole or not
+/// at all.
+#[tauri::command]
+async fn create_children(
+    parent_id: String,
+    date_str: String,
+    contents: Vec<String>,
+    node_type: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<NodeId>, AppError> {
+    log_command(
+        "create_children",
+        &format!(
+            "parent_id: {}, date: {}, count: {}, type: {}",
+            parent_id,
+            date_str,
+            contents.len(),
+            node_type
+        ),
+    );
+
+    validate_child_contents(&contents)?;
+    let date = parse_date(&date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+
+    let parent = load_node(service, &parent_id).await?;
+
+    // Embedding is the step most likely to fail, so it is done for the whole batch first
+    let dimension = embedding_dimension(service).await?;
+    let mut embeddings = Vec::with_capacity(contents.len());
+    for content in &contents {
+        let embedding = service
+            .generate_embedding(content)
+            .await
+            .map_err(|e| classify_service_error(&e, "Failed to embed child content"))?;
+        check_generated_embedding(&embedding, dimension)?;
+        embeddings.push(embedding);
+    }
+
+    // New children go after any existing ones
+    let existing_children: Vec<Node> = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?
+        .into_iter()
+        .filter(|n| n.parent_id.as_ref().map(|p| &p.0) == Some(&parent.id.0))
+        .collect();
+    let last_existing = hierarchy::order_siblings(existing_children)
+        .pop()
+        .map(|n| n.id);
+
+    let planned = plan_child_chain(contents, last_existing);
+    let parent_node_id = &parent.id;
+    let node_type = &node_type;
+    let created = create_all_or_roll_back(
+        planned.into_iter().zip(embeddings).collect(),
+        |(child, embedding): (PlannedChild, Vec<f32>)| async move {
+            service
+                .create_node_for_date_with_embedding(
+                    child.node_id.clone(),
+                    date,
+                    &child.content,
+                    parse_node_type(node_type),
+                    None,
+                    Some(parent_node_id.clone()),
+                    embedding,
+                )
+                .await
+                .map_err(|e| classify_service_error(&e, "Failed to create child"))?;
+            if child.before_sibling.is_some() {
+                service
+                    .update_sibling_order(&child.node_id, None, child.before_sibling.as_ref())
+                    .await
+                    .map_err(|e| classify_service_error(&e, "Failed to order child"))?;
+            }
+            Ok(child.node_id)
+        },
+        |node_id| async move {
+            service
+                .delete_node_with_children_transfer(&node_id, Vec::new(), None)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        },
+    )
+    .await
+    .map_err(|e| {
+        AppError::NodeOperation(format!("Failed to create children of {}: {}", parent_id, e))
+    })?;
+    state.note_node_date(date).await;
+
+    log::info!(
+        "Created {} children under node {}",
+        created.len(),
+        parent_id
+    );
+    Ok(created)
+}
+
+/// Copy a subtree as plain text: the Markdown outline of the node and its descendants
+#[tauri::command]
+async fn get_subtree_text(
+    root_node_id: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    log_command("get_subtree_text", &format!("root: {}", root_node_id));
+
+    let service = &state.get_service().await?;
+
+    let subtree = hierarchy::collect_subtree(load_all_nodes(service).await?, &root_node_id);
+    if subtree.is_empty() {
+        return Err(AppError::NotFound(format!("Node {}", root_node_id)));
+    }
+
+    let node_count = subtree.len();
+    let text = render::render_markdown_outline(&hierarchy::build_outline(subtree));
+
+    log::info!(
+        "Rendered subtree {} ({} nodes) as text",
+        root_node_id,
+        node_count
+    );
+    Ok(text)
+}
+
+/// Deep-copy a node and its descendants onto another date. Every copy gets a fresh id;
+/// types, metadata and sibling order are kept, and the copied root is placed at the top
+/// level of the target date. Returns the id of the new root.
+#[tauri::command]
+async fn duplicate_node(
+    node_id: String,
+    target_date_str: String,
+    state: State<'_, AppState>,
+) -> Result<NodeId, AppError> {
+    log_command(
+        "duplicate_node",
+        &format!("node_id: {}, target_date: {}", node_id, target_date_str),
+    );
+
+    let date = parse_date(&target_date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+
+    let subtree = hierarchy::collect_subtree(load_all_nodes(service).await?, &node_id);
+    if subtree.is_empty() {
+        return Err(AppError::NotFound(format!("Node {}", node_id)));
+    }
+    if subtree[0].r#type == "date" {
+        return Err(AppError::InvalidInput(
+            "Date nodes cannot be duplicated".to_string(),
+        ));
+    }
+
+    let requests = plan_duplicate(subtree, MAX_DUPLICATE_DEPTH)?;
+    validate_batch_requests(&requests, state.config().await.max_metadata_bytes)?;
+    let outcomes = create_requested_nodes(requests, |request| {
+        create_from_request(service, date, request)
+    })
+    .await;
+    state.note_node_date(date).await;
+    let created = created_ids(outcomes)?;
+
+    log::info!(
+        "Duplicated node {} ({} nodes) onto {}",
+        node_id,
+        created.len(),
+        target_date_str
+    );
+    created
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::NodeOperation("Duplicate produced no nodes".to_string()))
+}
+
+#[tauri::command]
+async fn find_empty_nodes(state: State<'_, AppState>) -> Result<Vec<Node>, AppError> {
+    log_command("find_empty_nodes", "");
+
+    let service = &state.get_service().await?;
+
+    let empty = find_empty_leaf_nodes(load_all_nodes(service).await?);
+
+    log::info!("Found {} empty nodes eligible for cleanup", empty.len());
+    Ok(empty)
+}
+
+#[tauri::command]
+async fn get_database_version(state: State<'_, AppState>) -> Result<String, AppError> {
+    log_command("get_database_version", "");
+
+    let version = schema::read_schema_version(&state.config().await.database_path)?;
+    let described = schema::describe_schema_version(version);
+
+    log::info!("Database schema version: {}", described);
+    Ok(described)
+}
+
+/// Apply forward schema migrations and record the new version. Safe to run repeatedly:
+/// an up-to-date database is left untouched.
+#[tauri::command]
+async fn migrate_database(
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<schema::MigrationSummary, AppError> {
+    log_command("migrate_database", "");
+
+    let db_dir = &state.config().await.database_path;
+    let from_version = schema::read_schema_version(db_dir)?;
+    let mut summary = schema::MigrationSummary {
+        from_version: schema::describe_schema_version(from_version),
+        to_version: schema::CURRENT_SCHEMA_VERSION.to_string(),
+        already_current: false,
+        root_ids_backfilled: 0,
+        metadata_normalized: 0,
+    };
+
+    if from_version.is_some_and(|v| v >= schema::CURRENT_SCHEMA_VERSION) {
+        summary.already_current = true;
+        summary.to_version = summary.from_version.clone();
+        log::info!(
+            "Database already at schema version {}",
+            summary.from_version
+        );
+        return Ok(summary);
+    }
+
+    let service = &state.get_service().await?;
+
+    let plan = schema::plan_migration(&load_all_nodes(service).await?, from_version);
+    if plan.is_empty() {
+        // Nothing to rewrite, but the version still needs recording so later runs skip
+        // the full scan
+        schema::write_schema_version(db_dir, schema::CURRENT_SCHEMA_VERSION)?;
+        log::info!(
+            "No nodes needed migrating from {}; recorded schema version {}",
+            summary.from_version,
+            summary.to_version
+        );
+        return Ok(summary);
+    }
+    let total = plan.root_id_backfills.len() + plan.metadata_rewrites.len();
+    let mut processed = 0;
+
+    for (node_id, root_id) in &plan.root_id_backfills {
+        service
+            .update_node_root(node_id, root_id)
+            .await
+            .map_err(|e| {
+                classify_service_error(
+                    &e,
+                    &format!("Failed to backfill root_id for node {}", node_id),
+                )
+            })?;
+        summary.root_ids_backfilled += 1;
+        processed += 1;
+        let _ = app.emit(
+            "migration-progress",
+            ProgressEvent {
+                processed,
+                total,
+                node_id: Some(node_id.0.clone()),
+            },
+        );
+    }
+
+    for (node_id, metadata) in &plan.metadata_rewrites {
+        store_node_metadata(&state, service, node_id, metadata.clone()).await?;
+        summary.metadata_normalized += 1;
+        processed += 1;
+        let _ = app.emit(
+            "migration-progress",
+            ProgressEvent {
+                processed,
+                total,
+                node_id: Some(node_id.0.clone()),
+            },
+        );
+    }
+
+    // Only record the new version once every step has succeeded
+    schema::write_schema_version(db_dir, schema::CURRENT_SCHEMA_VERSION)?;
+
+    log::info!(
+        "Migrated database from {} to {}: {} root_ids backfilled, {} metadata normalized",
+        summary.from_version,
+        summary.to_version,
+        summary.root_ids_backfilled,
+        summary.metadata_normalized
+    );
+    Ok(summary)
+}
+
+/// Rank every embedded node by cosine similarity to an arbitrary passage of text. Unlike
+/// `semantic_search` there is no question framing; the text is embedded as-is.
+#[tauri::command]
+async fn rank_nodes_by_text(
+    reference: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchResult>, AppError> {
+    log_command(
+        "rank_nodes_by_text",
+        &format!("reference_len: {}, limit: {}", reference.len(), limit),
+    );
+
+    if reference.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Reference text cannot be empty".to_string(),
+        ));
+    }
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let reference_embedding = service
+        .generate_embedding(&reference)
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to embed reference text"))?;
+
+    let nodes: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(|n| n.r#type != "date")
+        .collect();
+    let embedded = load_embeddings(service, nodes).await?;
+    let results = rank_by_similarity(&reference_embedding, embedded, limit);
+
+    log::info!(
+        "Ranked nodes by reference text, returning {}",
+        results.len()
+    );
+    Ok(results)
+}
+
+/// Length of the embeddings the current model produces, for the UI to check vectors
+/// against before sending them. The engine's reported dimension is confirmed with a
+/// sample embedding, so a model swap can't leave the two out of step unnoticed.
+#[tauri::command]
+async fn get_embedding_dimension(state: State<'_, AppState>) -> Result<usize, AppError> {
+    log_command("get_embedding_dimension", "");
+
+    let service = &state.get_service().await?;
+    let reported = embedding_dimension(service).await?;
+    let sample = service
+        .generate_embedding(EMBEDDING_SAMPLE_TEXT)
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to generate sample embedding"))?;
+    let dimension = confirm_embedding_dimension(reported, &sample)?;
+
+    log::info!("Embedding dimension: {}", dimension);
+    Ok(dimension)
+}
+
+/// "More like this": nodes most similar to an existing node, for a related-notes panel.
+/// Uses the node's stored embedding, or embeds its text when it has none. The node itself
+/// is never part of the results.
+#[tauri::command]
+async fn find_similar_nodes(
+    node_id: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchResult>, AppError> {
+    log_command(
+        "find_similar_nodes",
+        &format!("node_id: {}, limit: {}", node_id, limit),
+    );
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let source = load_node(service, &node_id).await?;
+    let dimension = embedding_dimension(service).await?;
+    // An embedding left over from a different model can't be compared, so re-embed instead
+    let stored = service
+        .get_node_embedding(&source.id)
+        .await
+        .map_err(|e| {
+            classify_store_error(
+                &e,
+                &format!("Failed to load embedding for node {}", node_id),
+            )
+        })?
+        .filter(|v| v.len() == dimension);
+    let reference = match stored {
+        Some(vector) => vector,
+        None => {
+            let text = source
+                .content
+                .as_str()
+                .filter(|c| !c.trim().is_empty())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(format!(
+                        "Node {} has no embedding or text to compare",
+                        node_id
+                    ))
+                })?;
+            service.generate_embedding(text).await.map_err(|e| {
+                classify_service_error(&e, &format!("Failed to embed node {}", node_id))
+            })?
+        }
+    };
+
+    let candidates: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(|n| n.r#type != "date")
+        .collect();
+    let embedded = load_embeddings(service, candidates).await?;
+    let results = rank_similar_nodes(&node_id, &reference, embedded, limit);
+
+    log::info!("Found {} nodes similar to {}", results.len(), node_id);
+    Ok(results)
+}
+
+/// Audit existing nodes for metadata larger than the configured limit
+#[tauri::command]
+async fn find_oversized_metadata(state: State<'_, AppState>) -> Result<Vec<Node>, AppError> {
+    log_command("find_oversized_metadata", "");
+
+    let limit = state.config().await.max_metadata_bytes;
+
+    let service = &state.get_service().await?;
+
+    let oversized: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(|n| has_oversized_metadata(n, limit))
+        .collect();
+
+    log::info!(
+        "Found {} nodes with metadata over {} bytes",
+        oversized.len(),
+        limit
+    );
+    Ok(oversized)
+}
+
+#[tauri::command]
+async fn export_date_conversations(
+    date_str: String,
+    dest_path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    log_command(
+        "export_date_conversations",
+        &format!("date: {}, dest: {}", date_str, dest_path),
+    );
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let dest = std::path::Path::new(&dest_path);
+    if dest.is_dir()
+        || dest
+            .parent()
+            .is_some_and(|p| !p.as_os_str().is_empty() && !p.is_dir())
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Destination must be a file path in an existing directory: {}",
+            dest_path
+        )));
+    }
+
+    let service = &state.get_service().await?;
+
+    let mut chats: Vec<Node> = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?
+        .into_iter()
+        .filter(render::is_ai_chat_node)
+        .collect();
+    chats.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    let transcript = render::render_conversation_transcript(&date_str, &chats);
+    std::fs::write(dest, transcript)
+        .map_err(|e| AppError::Internal(format!("Failed to write transcript: {}", e)))?;
+
+    log::info!(
+        "Exported {} AI conversations for {} to {}",
+        chats.len(),
+        date_str,
+        dest_path
+    );
+    Ok(chats.len())
+}
+
+#[tauri::command]
+async fn get_tag_counts_for_range(
+    start: String,
+    end: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<TagCount>, AppError> {
+    log_command(
+        "get_tag_counts_for_range",
+        &format!("start: {}, end: {}", start, end),
+    );
+
+    let start_date = parse_date(&start, "start date")?;
+    let end_date = parse_date(&end, "end date")?;
+    if start_date > end_date {
+        return Err(AppError::InvalidInput(
+            "Start date must not be after end date".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let counts = count_tags_in_range(&load_all_nodes(service).await?, start_date, end_date);
+
+    log::info!(
+        "Counted {} distinct tags between {} and {}",
+        counts.len(),
+        start,
+        end
+    );
+    Ok(counts)
+}
+
+/// Store a node together with a caller-supplied embedding instead of computing one
+#[tauri::command]
+async fn create_node_with_embedding(
+    date_str: String,
+    content: String,
+    embedding: Vec<f32>,
+    node_type: String,
+    parent_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<NodeId, AppError> {
+    log_command(
+        "create_node_with_embedding",
+        &format!(
+            "date: {}, content_len: {}, embedding_len: {}, type: {}, parent_id: {:?}",
+            date_str,
+            content.len(),
+            embedding.len(),
+            node_type,
+            parent_id
+        ),
+    );
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+
+    let expected_dimension = embedding_dimension(service).await?;
+    validate_embedding(&embedding, expected_dimension)?;
+
+    let node_id = NodeId::new();
+    service
+        .create_node_for_date_with_embedding(
+            node_id.clone(),
+            date,
+            &content,
+            parse_node_type(&node_type),
+            None,
+            parent_id.map(NodeId::from_string),
+            embedding,
+        )
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to store node with embedding"))?;
+    state.note_node_date(date).await;
+
+    log::info!(
+        "Created node {} for date {} with a supplied {}-dim embedding",
+        node_id,
+        date_str,
+        expected_dimension
+    );
+    Ok(node_id)
+}
+
+/// The most recently edited nodes across all dates, newest first. The store has no
+/// global recency index, so this scans every node; fine at notebook scale, but it is
+/// O(n log n) per call and should move to a store query if vaults grow large.
+#[tauri::command]
+async fn get_recent_nodes(limit: usize, state: State<'_, AppState>) -> Result<Vec<Node>, AppError> {
+    log_command("get_recent_nodes", &format!("limit: {}", limit));
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+    let recent = find_recent_nodes(load_all_nodes(service).await?, limit);
+
+    log::info!("Found {} recently edited nodes", recent.len());
+    Ok(recent)
+}
+
+/// Nodes untouched for at least `older_than_days`, oldest first
+#[tauri::command]
+async fn get_stale_nodes(
+    older_than_days: u32,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, AppError> {
+    log_command(
+        "get_stale_nodes",
+        &format!("older_than_days: {}, limit: {}", older_than_days, limit),
+    );
+
+    if older_than_days == 0 || older_than_days > 3650 {
+        return Err(AppError::InvalidInput(
+            "Threshold must be between 1 and 3650 days".to_string(),
+        ));
+    }
+
+    if limit == 0 || limit > 100 {
+        return Err(AppError::InvalidInput(
+            "Limit must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let stale = find_stale_nodes(
+        load_all_nodes(service).await?,
+        chrono::Utc::now(),
+        older_than_days,
+        limit,
+    );
+
+    log::info!(
+        "Found {} nodes not updated in {} days",
+        stale.len(),
+        older_than_days
+    );
+    Ok(stale)
+}
+
+/// Attach (or replace) a reminder on a node. Setting a new time clears any earlier dismissal.
+#[tauri::command]
+async fn set_node_reminder(
+    node_id: String,
+    remind_at: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "set_node_reminder",
+        &format!("node_id: {}, remind_at: {}", node_id, remind_at),
+    );
+
+    let remind_at = parse_reminder_time(&remind_at)?;
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    let metadata = with_reminder(node.metadata.as_ref(), remind_at);
+
+    store_node_metadata(&state, service, &node.id, metadata).await?;
+
+    log::info!(
+        "Set reminder for node {} at {}",
+        node_id,
+        remind_at.to_rfc3339()
+    );
+    Ok(())
+}
+
+/// Tasks across all dates for an agenda view, soonest due first and by priority within a
+/// due date. Tasks without a due date or priority sort after those with one.
+#[tauri::command]
+async fn get_tasks(filter: TaskFilter, state: State<'_, AppState>) -> Result<Vec<Node>, AppError> {
+    log_command("get_tasks", &format!("filter: {:?}", filter));
+
+    let service = &state.get_service().await?;
+
+    let tasks = select_tasks(load_all_nodes(service).await?, &filter)?;
+    log::info!("Found {} tasks matching {:?}", tasks.len(), filter);
+    Ok(tasks)
+}
+
+/// Flip a task's `completed` flag and return the new state
+#[tauri::command]
+async fn toggle_task_completion(
+    node_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    log_command("toggle_task_completion", &format!("node_id: {}", node_id));
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    if node.r#type != TASK_NODE_TYPE {
+        return Err(AppError::InvalidInput(format!(
+            "Node {} is a {} node, not a task",
+            node_id, node.r#type
+        )));
+    }
+    let (metadata, completed) = with_completion_toggled(node.metadata.as_ref(), chrono::Utc::now());
+
+    store_node_metadata(&state, service, &node.id, metadata).await?;
+
+    log::info!(
+        "Task {} marked {}",
+        node_id,
+        if completed { "complete" } else { "incomplete" }
+    );
+    let date = lookup_node_date(service, &node_id).await;
+    emit_node_changed(&app, NodeChangeKind::Updated, &node_id, date);
+    Ok(completed)
+}
+
+/// Nodes whose reminder time has passed and that haven't been dismissed, earliest first.
+/// `now` defaults to the current time.
+#[tauri::command]
+async fn get_due_reminders(
+    now: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, AppError> {
+    log_command("get_due_reminders", &format!("now: {:?}", now));
+
+    let now = match now {
+        Some(now) => parse_reminder_time(&now)?,
+        None => chrono::Utc::now(),
+    };
+
+    let service = &state.get_service().await?;
+
+    let due = find_due_reminders(load_all_nodes(service).await?, now);
+
+    log::info!("Found {} due reminders", due.len());
+    Ok(due)
+}
+
+#[tauri::command]
+async fn dismiss_reminder(node_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    log_command("dismiss_reminder", &format!("node_id: {}", node_id));
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    if reminder_time(&node).is_none() {
+        return Err(AppError::InvalidInput(format!(
+            "Node {} has no reminder",
+            node_id
+        )));
+    }
+    let metadata = with_reminder_dismissed(node.metadata.as_ref());
+
+    store_node_metadata(&state, service, &node.id, metadata).await?;
+
+    log::info!("Dismissed reminder for node {}", node_id);
+    Ok(())
+}
+
+/// Point the app at another database and models directory without restarting. The
+/// service is torn down once in-flight commands release it and is recreated from the new
+/// paths by the next command. Fails, keeping the current paths, if commands still hold the
+/// service after `SERVICE_RELEASE_TIMEOUT`.
+#[tauri::command]
+async fn reconfigure_service(
+    database_path: String,
+    models_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "reconfigure_service",
+        &format!("database: {}, models: {}", database_path, models_path),
+    );
+
+    // Holding the service lock keeps new commands from starting against the old service.
+    // Commands already running hold their own clone of it, so wait for those to finish.
+    let mut service_guard = state.nodespace_service.lock().await;
+    wait_for_release(service_guard.as_ref(), SERVICE_RELEASE_TIMEOUT).await?;
+    state.forget_date_bounds().await;
+    let mut config = state.config.lock().await;
+    let new_config = AppConfig {
+        database_path: database_path.into(),
+        models_path: models_path.into(),
+        ..config.clone()
+    };
+    swap_config(&mut config, &mut *service_guard, new_config)?;
+
+    log::info!(
+        "Service reconfigured to database {} and models {}; it will reinitialize on next use",
+        config.database_path.display(),
+        config.models_path.display()
+    );
+    Ok(())
+}
+
+/// Move a node under a new parent (or to the root when `None`), placing it after
+/// `before_sibling_id`. Moves that would put a node under itself or its own descendant
+/// are rejected before anything is written.
+#[tauri::command]
+async fn move_node(
+    node_id: String,
+    new_parent_id: Option<String>,
+    before_sibling_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "move_node",
+        &format!(
+            "node_id: {}, new_parent_id: {:?}, before_sibling_id: {:?}",
+            node_id, new_parent_id, before_sibling_id
+        ),
+    );
+
+    if before_sibling_id.as_deref() == Some(node_id.as_str()) {
+        return Err(AppError::InvalidInput(
+            "A node cannot be placed after itself".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    ensure_no_cycle(service, &node_id, new_parent_id.as_deref()).await?;
+
+    let parent_node_id = new_parent_id
+        .as_ref()
+        .map(|id| NodeId::from_string(id.clone()));
+    let before_sibling_node_id = before_sibling_id
+        .as_ref()
+        .map(|id| NodeId::from_string(id.clone()));
+
+    service
+        .set_node_parent(&node.id, parent_node_id.as_ref())
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to update parent for move"))?;
+    service
+        .update_sibling_order(&node.id, None, before_sibling_node_id.as_ref())
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to update sibling order for move"))?;
+
+    log::info!(
+        "Moved node {} to parent {:?}, before sibling {:?}",
+        node_id,
+        new_parent_id,
+        before_sibling_id
+    );
+    Ok(())
+}
+
+/// The ancestors of a node, date root first, for breadcrumbs. A root-level node has none.
+#[tauri::command]
+async fn get_node_ancestors(
+    node_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, AppError> {
+    log_command("get_node_ancestors", &format!("node_id: {}", node_id));
+
+    let service = &state.get_service().await?;
+
+    // The chain starts with the node itself, so a missing node surfaces as NotFound here
+    let chain = load_parent_chain(service, &node_id).await?;
+    let ancestors = hierarchy::breadcrumb_trail(&chain, &node_id)?;
+
+    log::info!("Node {} has {} ancestors", node_id, ancestors.len());
+    Ok(ancestors)
+}
+
+/// Depth of a node in its outline, counted from the root date node (depth 0)
+#[tauri::command]
+async fn get_node_depth(node_id: String, state: State<'_, AppState>) -> Result<u32, AppError> {
+    log_command("get_node_depth", &format!("node_id: {}", node_id));
+
+    let service = &state.get_service().await?;
+
+    let nodes = load_all_nodes(service).await?;
+    let depth = hierarchy::ancestor_chain(&nodes, &node_id)?.len() as u32;
+
+    log::info!("Node {} is at depth {}", node_id, depth);
+    Ok(depth)
+}
+
+/// Report nodes whose content contains the Unicode replacement character or control
+/// characters. With `repair`, control characters are stripped from the stored content;
+/// replacement characters are left for the user since the original text is unknown.
+#[tauri::command]
+async fn find_encoding_issues(
+    repair: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, AppError> {
+    let repair = repair.unwrap_or(false);
+    log_command("find_encoding_issues", &format!("repair: {}", repair));
+
+    let service = &state.get_service().await?;
+
+    let flagged: Vec<Node> = load_all_nodes(service)
+        .await?
+        .into_iter()
+        .filter(|n| n.content.as_str().is_some_and(has_encoding_issues))
+        .collect();
+
+    if repair {
+        for node in &flagged {
+            let content = node.content.as_str().unwrap_or_default();
+            let cleaned = strip_control_chars(content);
+            if cleaned != content {
+                service.update_node(&node.id, &cleaned).await.map_err(|e| {
+                    classify_service_error(&e, &format!("Failed to repair node {}", node.id))
+                })?;
+            }
+        }
+    }
+
+    log::info!(
+        "Found {} nodes with encoding issues (repair: {})",
+        flagged.len(),
+        repair
+    );
+    Ok(flagged)
+}
+
+/// Streaming variant of `process_query`. Tokens are forwarded over `channel` as the model
+/// produces them. If the frontend drops the channel, generation is stopped instead of
+/// running to completion.
+#[tauri::command]
+async fn process_query_stream(
+    question: String,
+    model: Option<String>,
+    channel: tauri::ipc::Channel<QueryStreamEvent>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "process_query_stream",
+        &format!("question: {}, model: {:?}", question, model),
+    );
+
+    if question.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Question cannot be empty".to_string(),
+        ));
+    }
+
+    let model = resolve_model(model, &*state.settings.lock().await);
+    if let Some(model) = model.as_deref() {
+        validate_model_name(model, &available_models(&state.config().await.models_path))?;
+    }
+
+    let service = &state.get_service().await?;
+
+    let started = std::time::Instant::now();
+    let mut cancelled = false;
+    // Returning false from the token callback asks the engine to stop generating
+    let query_response = service
+        .process_query_streaming(&question, model.as_deref(), |token: &str| {
+            let sent = channel.send(QueryStreamEvent::Token {
+                text: token.to_string(),
+            });
+            cancelled = sent.is_err();
+            !cancelled
+        })
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to process query"))?;
+
+    if cancelled {
+        log::info!("Query stream cancelled by the frontend");
+        return Ok(());
+    }
+    state.query_latencies.lock().await.record(started.elapsed());
+
+    let response = QueryResponse {
+        answer: query_response.answer,
+        sources: query_sources(service, &question).await,
+        confidence: query_response.confidence as f64,
+    };
+    channel
+        .send(QueryStreamEvent::Done { response })
+        .map_err(|e| AppError::Internal(format!("Failed to send final query response: {}", e)))?;
+
+    log::info!("Streamed query processed successfully");
+    Ok(())
+}
+
+/// Top semantic matches for a question, shaped as query sources with snippets of the
+/// passage most relevant to it
+async fn query_sources(service: &NodeSpaceServiceImpl, question: &str) -> Vec<SearchResult> {
+    let search_results = service
+        .semantic_search(question, 5)
+        .await
+        .unwrap_or_default();
+
+    search_results
+        .into_iter()
+        .map(|search_result| {
+            let snippet = create_source_snippet(&search_result.node, question);
+            SearchResult {
+                node: search_result.node,
+                score: search_result.score as f64,
+                snippet,
+            }
+        })
+        .collect()
+}
+
+/// Create many nodes for one date with one service handle. Requests are processed in
+/// order, so siblings can reference nodes created earlier in the batch. Each request's
+/// outcome is reported, so one failure doesn't hide which nodes were created.
+#[tauri::command]
+async fn create_nodes_for_date_batch(
+    date_str: String,
+    nodes: Vec<NodeCreateRequest>,
+    state: State<'_, AppState>,
+) -> Result<Vec<(String, Result<(), String>)>, AppError> {
+    log_command(
+        "create_nodes_for_date_batch",
+        &format!("date: {}, count: {}", date_str, nodes.len()),
+    );
+
+    let date = parse_date(&date_str, "date format")?;
+
+    validate_batch_requests(&nodes, state.config().await.max_metadata_bytes)?;
+
+    let service = &state.get_service().await?;
+
+    let results =
+        create_requested_nodes(nodes, |request| create_from_request(service, date, request)).await;
+    state.note_node_date(date).await;
+
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    log::info!(
+        "Batch created {} of {} nodes for date {}",
+        results.len() - failed,
+        results.len(),
+        date_str
+    );
+    Ok(results)
+}
+
+/// Turn a pasted Markdown outline into nodes under the date. Returns the new ids in
+/// document order.
+#[tauri::command]
+async fn import_markdown(
+    date_str: String,
+    markdown: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<NodeId>, AppError> {
+    log_command(
+        "import_markdown",
+        &format!("date: {}, length: {}", date_str, markdown.len()),
+    );
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let requests = markdown::parse_markdown_outline(&markdown);
+    if requests.is_empty() {
+        return Ok(Vec::new());
+    }
+    validate_batch_requests(&requests, state.config().await.max_metadata_bytes)?;
+
+    let service = &state.get_service().await?;
+
+    let outcomes = create_requested_nodes(requests, |request| {
+        create_from_request(service, date, request)
+    })
+    .await;
+    state.note_node_date(date).await;
+    let created = created_ids(outcomes)?;
+
+    log::info!(
+        "Imported {} Markdown items for date {}",
+        created.len(),
+        date_str
+    );
+    Ok(created)
+}
+
+/// Run `create` for each request in order, so later requests can point at earlier ones,
+/// pairing each request id with its own outcome. A request whose parent or previous
+/// sibling failed earlier in the batch is skipped rather than attached to nothing.
+async fn create_requested_nodes<F, Fut>(
+    nodes: Vec<NodeCreateRequest>,
+    mut create: F,
+) -> Vec<(String, Result<(), String>)>
+where
+    F: FnMut(NodeCreateRequest) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    let mut failed = std::collections::HashSet::new();
+    let mut results = Vec::with_capacity(nodes.len());
+    for request in nodes {
+        let node_id = request.node_id.clone();
+        let blocked_by = [&request.parent_id, &request.before_sibling_id]
+            .into_iter()
+            .flatten()
+            .find(|id| failed.contains(*id))
+            .cloned();
+        let result = match blocked_by {
+            Some(id) => Err(format!("Skipped because node {} was not created", id)),
+            None => create(request).await,
+        };
+        if let Err(e) = &result {
+            log::warn!("Failed to create node {}: {}", node_id, e);
+            failed.insert(node_id.clone());
+        }
+        results.push((node_id, result));
+    }
+    results
+}
+
+/// Store one batch request on `date` under its requested id
+async fn create_from_request(
+    service: &NodeSpaceServiceImpl,
+    date: NaiveDate,
+    request: NodeCreateRequest,
+) -> Result<(), String> {
+    let node_type = request.node_type.as_deref().unwrap_or("text");
+    service
+        .create_node_for_date_with_id(
+            NodeId::from_string(request.node_id),
+            date,
+            &request.content,
+            parse_node_type(node_type),
+            with_node_type_marker(node_type, request.metadata),
+            request.parent_id.map(NodeId::from_string),
+            request.before_sibling_id.map(NodeId::from_string),
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Ids of a batch that must succeed as a whole. On failure the error names the failed
+/// requests and the nodes that were created before it.
+fn created_ids(outcomes: Vec<(String, Result<(), String>)>) -> Result<Vec<NodeId>, AppError> {
+    let (created, failed): (Vec<_>, Vec<_>) = outcomes.into_iter().partition(|(_, r)| r.is_ok());
+    if let Some((node_id, Err(e))) = failed.first() {
+        return Err(AppError::NodeOperation(format!(
+            "Failed to create node {} ({} failed, created: [{}]): {}",
+            node_id,
+            failed.len(),
+            created
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            e
+        )));
+    }
+    Ok(created
+        .into_iter()
+        .map(|(id, _)| NodeId::from_string(id))
+        .collect())
+}
+
+#[tauri::command]
+async fn get_graph_snapshot(state: State<'_, AppState>) -> Result<GraphSnapshot, AppError> {
+    log_command("get_graph_snapshot", "");
+
+    let service = &state.get_service().await?;
+
+    let snapshot = build_graph_snapshot(load_all_nodes(service).await?, MAX_GRAPH_VERTICES);
+
+    log::info!(
+        "Built graph snapshot with {} vertices and {} edges (truncated: {})",
+        snapshot.vertices.len(),
+        snapshot.edges.len(),
+        snapshot.truncated
+    );
+    Ok(snapshot)
+}
+
+/// Rebuild snippets for previously found nodes so they center on `query` instead of the
+/// start of the content. Nodes that no longer exist are left out of the result.
+#[tauri::command]
+async fn resnippet(
+    node_ids: Vec<String>,
+    query: String,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, String>, AppError> {
+    log_command(
+        "resnippet",
+        &format!("count: {}, query: {}", node_ids.len(), query),
+    );
+
+    if query.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search query cannot be empty".to_string(),
+        ));
+    }
+
+    if node_ids.is_empty() || node_ids.len() > 100 {
+        return Err(AppError::InvalidInput(
+            "Between 1 and 100 node ids must be provided".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let mut snippets = HashMap::new();
+    for node_id in node_ids {
+        let node = service
+            .get_node(&NodeId::from_string(node_id.clone()))
+            .await
+            .map_err(|e| classify_store_error(&e, "Failed to load node"))?;
+        match node {
+            Some(node) => {
+                snippets.insert(node_id, create_query_snippet(&node, &query));
+            }
+            None => log::warn!("Skipping snippet for missing node {}", node_id),
+        }
+    }
+
+    log::info!("Regenerated {} snippets", snippets.len());
+    Ok(snippets)
+}
+
+/// Mean embedding of a node and its descendants, for comparing whole subtrees by topic.
+/// Nodes without a stored embedding are skipped.
+#[tauri::command]
+async fn get_subtree_centroid(
+    root_node_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<f32>, AppError> {
+    log_command(
+        "get_subtree_centroid",
+        &format!("root_node_id: {}", root_node_id),
+    );
+
+    let service = &state.get_service().await?;
+
+    let subtree = hierarchy::collect_subtree(load_all_nodes(service).await?, &root_node_id);
+    if subtree.is_empty() {
+        return Err(AppError::NotFound(format!("Node {}", root_node_id)));
+    }
+    let member_count = subtree.len();
+    let embedded = load_embeddings(service, subtree).await?;
+
+    let centroid = embedded_centroid(&embedded).ok_or_else(|| {
+        AppError::NotFound(format!("No embeddings in subtree of {}", root_node_id))
+    })?;
+
+    log::info!(
+        "Computed centroid of subtree {} from {} of {} nodes",
+        root_node_id,
+        embedded.len(),
+        member_count
+    );
+    Ok(centroid)
+}
+
+/// The most central note of a date: the node whose embedding is nearest the date's
+/// centroid. Dates without embedded nodes return `None`.
+#[tauri::command]
+async fn get_representative_node(
+    date_str: String,
+    state: State<'_, AppState>,
+) -> Result<Option<Node>, AppError> {
+    log_command("get_representative_node", &format!("date: {}", date_str));
+
+    let date = parse_date(&date_str, "date format")?;
+
+    let service = &state.get_service().await?;
+
+    let nodes: Vec<Node> = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?
+        .into_iter()
+        .filter(|n| n.r#type != "date")
+        .collect();
+    let representative = most_central_node(load_embeddings(service, nodes).await?);
+
+    log::info!(
+        "Representative node for {}: {:?}",
+        date_str,
+        representative.as_ref().map(|n| &n.id.0)
+    );
+    Ok(representative)
+}
+
+/// Export an inclusive date range without building one large payload. Nodes are read a
+/// date at a time and sent as `export-chunk` events tagged with `channel_id`, then a
+/// final `export-done` event reports the totals.
+#[tauri::command]
+async fn stream_export_range(
+    start: String,
+    end: String,
+    channel_id: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<ExportDoneEvent, AppError> {
+    log_command(
+        "stream_export_range",
+        &format!("start: {}, end: {}, channel_id: {}", start, end, channel_id),
+    );
+
+    let start_date = parse_date(&start, "start date")?;
+    let end_date = parse_date(&end, "end date")?;
+    if start_date > end_date {
+        return Err(AppError::InvalidInput(
+            "Start date must not be after end date".to_string(),
+        ));
+    }
+    if (end_date - start_date).num_days() >= MAX_EXPORT_RANGE_DAYS {
+        return Err(AppError::InvalidInput(format!(
+            "Export range cannot exceed {} days",
+            MAX_EXPORT_RANGE_DAYS
+        )));
+    }
+
+    let service = &state.get_service().await?;
+
+    let mut done = ExportDoneEvent {
+        channel_id: channel_id.clone(),
+        total_nodes: 0,
+        dates_exported: 0,
+    };
+    for date in start_date.iter_days().take_while(|d| *d <= end_date) {
+        let nodes = service
+            .get_nodes_for_date(date)
+            .await
+            .map_err(|e| classify_store_error(&e, &format!("Failed to get nodes for {}", date)))?;
+        if nodes.is_empty() {
+            continue;
+        }
+
+        let date_str = date.format("%Y-%m-%d").to_string();
+        for (ndjson, node_count) in ndjson_batches(&nodes, EXPORT_CHUNK_SIZE)? {
+            app.emit(
+                "export-chunk",
+                ExportChunkEvent {
+                    channel_id: channel_id.clone(),
+                    date: date_str.clone(),
+                    ndjson,
+                    node_count,
+                },
+            )
+            .map_err(|e| AppError::Internal(format!("Failed to emit export chunk: {}", e)))?;
+        }
+        done.total_nodes += nodes.len();
+        done.dates_exported += 1;
+    }
+
+    app.emit("export-done", done.clone())
+        .map_err(|e| AppError::Internal(format!("Failed to emit export completion: {}", e)))?;
+
+    log::info!(
+        "Streamed {} nodes across {} dates for export {}",
+        done.total_nodes,
+        done.dates_exported,
+        channel_id
+    );
+    Ok(done)
+}
+
+/// Give a node a manual sort rank, independent of its position in the sibling chain
+#[tauri::command]
+async fn set_node_sort_key(
+    node_id: String,
+    key: f64,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    log_command(
+        "set_node_sort_key",
+        &format!("node_id: {}, key: {}", node_id, key),
+    );
+
+    if !key.is_finite() {
+        return Err(AppError::InvalidInput(
+            "Sort key must be a finite number".to_string(),
+        ));
+    }
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    let metadata = with_sort_key(node.metadata.as_ref(), key);
+
+    store_node_metadata(&state, service, &node.id, metadata).await?;
+
+    log::info!("Set sort key {} for node {}", key, node_id);
+    Ok(())
+}
+
+/// The direct children of a node in sibling order, so the outline can be expanded one
+/// level at a time. A node without children yields an empty list.
+#[tauri::command]
+async fn get_node_children(
+    parent_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, AppError> {
+    log_command("get_node_children", &format!("parent_id: {}", parent_id));
+
+    let service = &state.get_service().await?;
+
+    let parent = load_node(service, &parent_id).await?;
+    let children = load_children(service, &parent).await?;
+
+    log::info!("Node {} has {} children", parent_id, children.len());
+    Ok(children)
+}
+
+/// Children of `parent_id` ordered by their sort keys. Children without a key follow the
+/// keyed ones, and ties keep their sibling-chain order.
+#[tauri::command]
+async fn get_children_sorted_by_key(
+    parent_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, AppError> {
+    log_command(
+        "get_children_sorted_by_key",
+        &format!("parent_id: {}", parent_id),
+    );
+
+    let service = &state.get_service().await?;
+
+    let parent = load_node(service, &parent_id).await?;
+    let children = load_children(service, &parent).await?;
+    let sorted = sort_by_sort_key(children);
+
+    log::info!("Sorted {} children of {} by key", sorted.len(), parent_id);
+    Ok(sorted)
+}
+
+/// Delete several nodes with one service handle. Each node is attempted independently and
+/// its outcome reported, so one failure doesn't hide which deletions went through.
+///
+/// `deletion_context` maps node ids to the same `childrenIds`/`childrenTransferredTo`
+/// context `delete_node` takes; nodes without an entry use the top-level object.
+#[tauri::command]
+async fn delete_nodes_batch(
+    node_ids: Vec<String>,
+    deletion_context: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<Vec<(String, Result<(), String>)>, AppError> {
+    log_command(
+        "delete_nodes_batch",
+        &format!("count: {}, context: {}", node_ids.len(), deletion_context),
+    );
+
+    if node_ids.is_empty() {
+        return Err(AppError::InvalidInput("No node ids provided".to_string()));
+    }
+
+    let service = &state.get_service().await?;
+
+    let results = delete_each(node_ids, |node_id| {
+        let context = deletion_context
+            .get(&node_id)
+            .unwrap_or(&deletion_context)
+            .clone();
+        async move {
+            let node = load_node(service, &node_id).await?;
+            let (children_ids, children_transferred_to) = parse_deletion_context(&context);
+            service
+                .delete_node_with_children_transfer(
+                    &node.id,
+                    children_ids,
+                    children_transferred_to.as_ref(),
+                )
+                .await
+                .map_err(|e| format!("Failed to delete node: {}", e))
+        }
+    })
+    .await;
+
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    log::info!(
+        "Batch deleted {} of {} nodes",
+        results.len() - failed,
+        results.len()
+    );
+    Ok(results)
+}
+
+/// Children to re-parent and their new parent, from a frontend deletion context
+fn parse_deletion_context(deletion_context: &serde_json::Value) -> (Vec<NodeId>, Option<NodeId>) {
+    let children_ids: Vec<NodeId> = deletion_context
+        .get("childrenIds")
+        .and_then(|v| v.as_array())
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| NodeId::from_string(s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let children_transferred_to = deletion_context
+        .get("childrenTransferredTo")
+        .and_then(|v| v.as_str())
+        .map(|s| NodeId::from_string(s.to_string()));
+
+    (children_ids, children_transferred_to)
+}
+
+/// Run `create` for each item in order. If one fails, `remove` is run on the nodes already
+/// created, newest first, so the failure leaves nothing half-written behind. The error
+/// names any node the rollback could not remove.
+async fn create_all_or_roll_back<T, C, CFut, R, RFut>(
+    items: Vec<T>,
+    mut create: C,
+    mut remove: R,
+) -> Result<Vec<NodeId>, String>
+where
+    C: FnMut(T) -> CFut,
+    CFut: std::future::Future<Output = Result<NodeId, String>>,
+    R: FnMut(NodeId) -> RFut,
+    RFut: std::future::Future<Output = Result<(), String>>,
+{
+    let mut created = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let error = match create(item).await {
+            Ok(node_id) => {
+                created.push(node_id);
+                continue;
+            }
+            Err(e) => e,
+        };
+
+        let mut left_behind = Vec::new();
+        while let Some(node_id) = created.pop() {
+            if let Err(e) = remove(node_id.clone()).await {
+                log::warn!("Failed to roll back node {}: {}", node_id, e);
+                left_behind.push(node_id.0);
+            }
+        }
+        return Err(if left_behind.is_empty() {
+            format!(
+                "item {} failed and the batch was rolled back: {}",
+                index + 1,
+                error
+            )
+        } else {
+            format!(
+                "item {} failed and nodes [{}] could not be rolled back: {}",
+                index + 1,
+                left_behind.join(", "),
+                error
+            )
+        });
+    }
+    Ok(created)
+}
+
+/// Run `delete` for every id in order, pairing each id with its own outcome
+async fn delete_each<F, Fut>(
+    node_ids: Vec<String>,
+    mut delete: F,
+) -> Vec<(String, Result<(), String>)>
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    let mut results = Vec::with_capacity(node_ids.len());
+    for node_id in node_ids {
+        let result = delete(node_id.clone()).await;
+        if let Err(e) = &result {
+            log::warn!("Failed to delete node {}: {}", node_id, e);
+        }
+        results.push((node_id, result));
+    }
+    results
+}
+
+/// p50/p90/p99 latency over the most recent AI queries
+#[tauri::command]
+async fn get_latency_percentiles(
+    state: State<'_, AppState>,
+) -> Result<metrics::LatencyPercentiles, AppError> {
+    log_command("get_latency_percentiles", "");
+
+    let percentiles = state.query_latencies.lock().await.percentiles();
+
+    log::info!(
+        "Query latency over {} samples: p50 {:?}ms, p90 {:?}ms, p99 {:?}ms",
+        percentiles.sample_count,
+        percentiles.p50_ms,
+        percentiles.p90_ms,
+        percentiles.p99_ms
+    );
+    Ok(percentiles)
+}
+
+/// Report initialization progress without starting the service or waiting on a command
+/// that is currently creating it.
+#[tauri::command]
+async fn get_service_status(state: State<'_, AppState>) -> Result<ServiceStatus, AppError> {
+    log_command("get_service_status", "");
+
+    let service = match state.nodespace_service.try_lock() {
+        Ok(guard) => guard.clone(),
+        Err(_) => return Ok(ServiceStatus::not_started("Service is starting")),
+    };
+    let status = match service {
+        Some(service) => ServiceStatus::created(service.is_ready().await),
+        None => ServiceStatus::not_started("Service has not been started"),
+    };
+
+    log::debug!("Service status: {:?}", status);
+    Ok(status)
+}
+
+/// Compare the frontend's idea of a node's parent and predecessor with stored state. With
+/// `repair`, the stored position is updated to match the frontend, which is what the user
+/// sees; otherwise the report lets the frontend refresh its own view.
+#[tauri::command]
+async fn reconcile_node(
+    node_id: String,
+    expected_parent_id: Option<String>,
+    expected_before_sibling: Option<String>,
+    repair: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ReconcileReport, AppError> {
+    let repair = repair.unwrap_or(false);
+    log_command(
+        "reconcile_node",
+        &format!(
+            "node_id: {}, expected_parent_id: {:?}, expected_before_sibling: {:?}, repair: {}",
+            node_id, expected_parent_id, expected_before_sibling, repair
+        ),
+    );
+
+    let service = &state.get_service().await?;
+
+    let node = load_node(service, &node_id).await?;
+    let mut report = compare_hierarchy(&node, expected_parent_id, expected_before_sibling);
+
+    if repair && !report.in_sync() {
+        let parent = report.expected_parent_id.clone().map(NodeId::from_string);
+        let before_sibling = report
+            .expected_before_sibling
+            .clone()
+            .map(NodeId::from_string);
+        if !report.parent_matches {
+            service
+                .set_node_parent(&node.id, parent.as_ref())
+                .await
+                .map_err(|e| {
+                    classify_service_error(
+                        &e,
+                        &format!("Failed to repair parent of node {}", node_id),
+                    )
+                })?;
+        }
+        service
+            .update_sibling_order(&node.id, None, before_sibling.as_ref())
+            .await
+            .map_err(|e| {
+                classify_service_error(
+                    &e,
+                    &format!("Failed to repair sibling order of node {}", node_id),
+                )
+            })?;
+        report.repaired = true;
+    }
+
+    if report.in_sync() {
+        log::info!("Node {} hierarchy matches the frontend", node_id);
+    } else {
+        log::warn!(
+            "Node {} hierarchy drifted: stored parent {:?}, before {:?}; frontend parent {:?}, before {:?} (repaired: {})",
+            node_id,
+            report.stored_parent_id,
+            report.stored_before_sibling,
+            report.expected_parent_id,
+            report.expected_before_sibling,
+            report.repaired
+        );
+    }
+    Ok(report)
+}
+
+/// Write a node and its descendants to `dest_path` as a portable bundle directory that
+/// `import_subtree_bundle` can load into another vault. Embeddings are left out unless
+/// `include_embeddings` is set.
+#[tauri::command]
+async fn export_subtree_bundle(
+    root_node_id: String,
+    dest_path: String,
+    include_embeddings: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<bundle::BundleSummary, AppError> {
+    let include_embeddings = include_embeddings.unwrap_or(false);
+    log_command(
+        "export_subtree_bundle",
+        &format!(
+            "root_node_id: {}, dest: {}, include_embeddings: {}",
+            root_node_id, dest_path, include_embeddings
+        ),
+    );
+
+    let service = &state.get_service().await?;
+
+    let subtree = hierarchy::collect_subtree(load_all_nodes(service).await?, &root_node_id);
+    if subtree.is_empty() {
+        return Err(AppError::NotFound(format!("Node {}", root_node_id)));
+    }
+
+    let mut entries = Vec::with_capacity(subtree.len());
+    for node in subtree {
+        let embedding = if include_embeddings {
+            service.get_node_embedding(&node.id).await.map_err(|e| {
+                classify_store_error(
+                    &e,
+                    &format!("Failed to load embedding for node {}", node.id),
+                )
+            })?
+        } else {
+            None
+        };
+        entries.push(bundle::BundleNode { node, embedding });
+    }
+
+    let summary = bundle::write_bundle(std::path::Path::new(&dest_path), &root_node_id, entries)?;
+
+    log::info!(
+        "Exported {} nodes and {} assets to bundle {}",
+        summary.node_count,
+        summary.asset_count,
+        summary.bundle_path
+    );
+    Ok(summary)
+}
+
+/// Recreate a bundle written by `export_subtree_bundle` on `target_date`, under `parent_id`
+/// when given. Nodes get fresh ids and bundled images are copied into the vault. Bundled
+/// embeddings are reused when they match the current model; otherwise they are regenerated.
+/// A failure part way through removes the nodes and images already imported.
+#[tauri::command]
+async fn import_subtree_bundle(
+    src_path: String,
+    target_date: String,
+    parent_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<NodeId>, AppError> {
+    log_command(
+        "import_subtree_bundle",
+        &format!(
+            "src: {}, target_date: {}, parent_id: {:?}",
+            src_path, target_date, parent_id
+        ),
+    );
+
+    let date = parse_date(&target_date, "date format")?;
+    let bundle_root = std::path::Path::new(&src_path);
+    let manifest = bundle::read_bundle(bundle_root)?;
+
+    let service = &state.get_service().await?;
+
+    // Imported nodes go after any existing children of the target parent
+    let (target_parent, first_predecessor) = match parent_id.as_deref() {
+        Some(parent_id) => {
+            let parent = load_node(service, parent_id).await?;
+            let existing_children: Vec<Node> = service
+                .get_nodes_for_date(date)
+                .await
+                .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?
+                .into_iter()
+                .filter(|n| n.parent_id.as_ref().map(|p| &p.0) == Some(&parent.id.0))
+                .collect();
+            let last = hierarchy::order_siblings(existing_children)
+                .pop()
+                .map(|n| n.id);
+            (Some(parent.id), last)
+        }
+        None => (None, None),
+    };
+
+    let assets_dir = imported_assets_directory(&state.config().await.database_path);
+    let planned = bundle::plan_import(
+        manifest,
+        bundle_root,
+        target_parent,
+        first_predecessor,
+        &assets_dir,
+    )?;
+    let expected_dimension = if planned.iter().any(|p| p.embedding.is_some()) {
+        Some(embedding_dimension(service).await?)
+    } else {
+        None
+    };
+
+    // Everything that can be checked is checked before the first write, so a bad bundle
+    // leaves nothing behind
+    validate_planned_imports(&planned, state.config().await.max_metadata_bytes)?;
+    let copied_assets: Vec<std::path::PathBuf> = planned
+        .iter()
+        .filter_map(|p| p.asset.as_ref().map(|(_, dest)| dest.clone()))
+        .collect();
+
+    let assets_dir = &assets_dir;
+    let result = create_all_or_roll_back(
+        planned,
+        |plan: bundle::PlannedImport| async move {
+            let fail = |e: String| format!("node {}: {}", plan.original_id, e);
+
+            if let Some((source, dest)) = plan.asset.as_ref() {
+                std::fs::create_dir_all(assets_dir)
+                    .and_then(|_| std::fs::copy(source, dest))
+                    .map_err(|e| fail(format!("failed to copy image asset: {}", e)))?;
+            }
+
+            let embedding = plan.embedding.filter(|e| {
+                expected_dimension.is_some_and(|dim| validate_embedding(e, dim).is_ok())
+            });
+            match embedding {
+                Some(embedding) => {
+                    service
+                        .create_node_for_date_with_embedding(
+                            plan.node_id.clone(),
+                            date,
+                            &plan.content,
+                            parse_node_type(&plan.node_type),
+                            plan.metadata,
+                            plan.parent_id,
+                            embedding,
+                        )
+                        .await
+                        .map_err(|e| fail(e.to_string()))?;
+                    if plan.before_sibling.is_some() {
+                        service
+                            .update_sibling_order(&plan.node_id, None, plan.before_sibling.as_ref())
+                            .await
+                            .map_err(|e| fail(e.to_string()))?;
+                    }
+                }
+                None => {
+                    service
+                        .create_node_for_date_with_id(
+                            plan.node_id.clone(),
+                            date,
+                            &plan.content,
+                            parse_node_type(&plan.node_type),
+                            plan.metadata,
+                            plan.parent_id,
+                            plan.before_sibling,
+                        )
+                        .await
+                        .map_err(|e| fail(e.to_string()))?;
+                }
+            }
+            Ok(plan.node_id)
+        },
+        |node_id| async move {
+            service
+                .delete_node_with_children_transfer(&node_id, Vec::new(), None)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        },
+    )
+    .await;
+
+    let created = match result {
+        Ok(created) => created,
+        Err(e) => {
+            for dest in copied_assets.iter().filter(|dest| dest.is_file()) {
+                if let Err(remove_error) = std::fs::remove_file(dest) {
+                    log::warn!(
+                        "Failed to remove imported asset {}: {}",
+                        dest.display(),
+                        remove_error
+                    );
+                }
+            }
+            return Err(AppError::NodeOperation(format!(
+                "Failed to import bundle {}: {}",
+                src_path, e
+            )));
+        }
+    };
+    state.note_node_date(date).await;
+
+    log::info!(
+        "Imported {} nodes from bundle {} into {}",
+        created.len(),
+        src_path,
+        target_date
+    );
+    Ok(created)
+}
+
+/// Load `node_id` and its ancestors by following `parent_id` links up to the root. Stops
+/// early at a missing parent or a node already visited, and fails once the chain grows
+/// past `MAX_ANCESTOR_WALK`.
+async fn load_parent_chain(
+    service: &NodeSpaceServiceImpl,
+    node_id: &str,
+) -> Result<Vec<Node>, AppError> {
+    let mut chain: Vec<Node> = Vec::new();
+    let mut next = Some(NodeId::from_string(node_id.to_string()));
+    while let Some(id) = next.take() {
+        if chain.iter().any(|n| n.id.0 == id.0) {
+            break;
+        }
+        if chain.len() > MAX_ANCESTOR_WALK {
+            return Err(AppError::NodeOperation(format!(
+                "Parent chain of node {} is longer than {} levels",
+                node_id, MAX_ANCESTOR_WALK
+            )));
+        }
+        let Some(node) = service
+            .get_node(&id)
+            .await
+            .map_err(|e| classify_store_error(&e, "Failed to load node"))?
+        else {
+            break;
+        };
+        next = node.parent_id.clone();
+        chain.push(node);
+    }
+    Ok(chain)
+}
+
+/// Compare an upsert request with the stored node. Metadata left out of the request is
+/// kept as is; a parent change always rewrites sibling order, since the old predecessor
+/// belongs to another list.
+fn upsert_changes(
+    existing: &Node,
+    content: &str,
+    metadata: Option<&serde_json::Value>,
+    parent_id: Option<&NodeId>,
+    before_sibling: Option<&NodeId>,
+) -> UpsertChanges {
+    let id_of = |id: Option<&NodeId>| id.map(|id| id.0.clone());
+    let parent = id_of(existing.parent_id.as_ref()) != id_of(parent_id);
+    UpsertChanges {
+        content: existing.content.as_str() != Some(content),
+        metadata: metadata.is_some_and(|m| existing.metadata.as_ref() != Some(m)),
+        parent,
+        sibling_order: parent || id_of(existing.before_sibling.as_ref()) != id_of(before_sibling),
+    }
+}
+
+/// The parent an `update_node_structure` operation will assign. Outdent always moves to
+/// the root; every other operation, including unrecognised ones, uses `parent_id`.
+fn structure_parent_target<'a>(operation: &str, parent_id: Option<&'a str>) -> Option<&'a str> {
+    match operation {
+        "outdent" => None,
+        _ => parent_id,
+    }
+}
+
+/// Fail when moving `node_id` under `new_parent_id` would make it its own ancestor
+async fn ensure_no_cycle(
+    service: &NodeSpaceServiceImpl,
+    node_id: &str,
+    new_parent_id: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(new_parent_id) = new_parent_id else {
+        return Ok(());
+    };
+    let chain = load_parent_chain(service, new_parent_id).await?;
+    hierarchy::check_reparent(&chain, node_id, new_parent_id)
+}
+
+/// The day a stored node belongs to, via its date root. Best effort: lookup failures
+/// yield `None` rather than failing the write that triggered the lookup.
+async fn lookup_node_date(service: &NodeSpaceServiceImpl, node_id: &str) -> Option<String> {
+    let node = load_node(service, node_id).await.ok()?;
+    node_day(service, &node)
+        .await
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+/// The day `node` belongs to: its own date for a date node, otherwise its date root's
+async fn node_day(service: &NodeSpaceServiceImpl, node: &Node) -> Option<NaiveDate> {
+    match node.root_id.as_ref() {
+        Some(root_id) if node.r#type != "date" => {
+            date_node_date(&load_node(service, &root_id.0).await.ok()?)
+        }
+        _ => date_node_date(node),
+    }
+}
+
+/// Direct children of `parent` in sibling order. Children share their parent's date root,
+/// so only that day is read; a parent whose day can't be resolved falls back to a scan of
+/// the whole vault.
+async fn load_children(
+    service: &NodeSpaceServiceImpl,
+    parent: &Node,
+) -> Result<Vec<Node>, AppError> {
+    let nodes = match node_day(service, parent).await {
+        Some(date) => service
+            .get_nodes_for_date(date)
+            .await
+            .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?,
+        None => load_all_nodes(service).await?,
+    };
+    Ok(hierarchy::direct_children(nodes, &parent.id.0))
+}
+
+fn emit_node_changed(
+    app: &AppHandle,
+    operation: NodeChangeKind,
+    node_id: &str,
+    date: Option<String>,
+) {
+    let event = NodeChangedEvent {
+        operation,
+        node_id: node_id.to_string(),
+        date,
+    };
+    if let Err(e) = app.emit(NODE_CHANGED_EVENT, event) {
+        log::warn!(
+            "Failed to emit {} for node {}: {}",
+            NODE_CHANGED_EVENT,
+            node_id,
+            e
+        );
+    }
+}
+
+async fn load_node(service: &NodeSpaceServiceImpl, node_id: &str) -> Result<Node, AppError> {
+    service
+        .get_node(&NodeId::from_string(node_id.to_string()))
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to load node"))?
+        .ok_or_else(|| AppError::NotFound(format!("Node {}", node_id)))
+}
+
+/// Load every node in the store. This is a full scan, so callers should only use it
+/// for aggregate views where no narrower query exists.
+async fn load_all_nodes(service: &NodeSpaceServiceImpl) -> Result<Vec<Node>, AppError> {
+    service
+        .get_all_nodes()
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to load nodes"))
+}
+
+/// Pair nodes with their stored embeddings, skipping nodes that have none.
+async fn load_embeddings(
+    service: &NodeSpaceServiceImpl,
+    nodes: Vec<Node>,
+) -> Result<Vec<(Node, Vec<f32>)>, AppError> {
+    let dimension = embedding_dimension(service).await?;
+    let mut embedded = Vec::with_capacity(nodes.len());
+    let mut stale = 0;
+    for node in nodes {
+        let embedding = service.get_node_embedding(&node.id).await.map_err(|e| {
+            classify_store_error(
+                &e,
+                &format!("Failed to load embedding for node {}", node.id),
+            )
+        })?;
+        match embedding.filter(|v| !v.is_empty()) {
+            Some(vector) if vector.len() == dimension => embedded.push((node, vector)),
+            Some(_) => stale += 1,
+            None => {}
+        }
+    }
+    if stale > 0 {
+        log::warn!(
+            "Skipped {} embeddings that don't match the model's {} dimensions",
+            stale,
+            dimension
+        );
+    }
+    Ok(embedded)
+}
+
+/// Flag a node just written through the service if it was stored without a vector, which
+/// is what happens when embedding generation fails. The write itself succeeded, so
+/// problems here are logged rather than returned.
+async fn flag_missing_embedding(
+    state: &AppState,
+    service: &NodeSpaceServiceImpl,
+    node_id: &NodeId,
+) {
+    let embedded = match service.get_node_embedding(node_id).await {
+        Ok(embedding) => embedding.is_some_and(|v| !v.is_empty()),
+        Err(e) => {
+            log::warn!("Could not check the embedding of node {}: {}", node_id, e);
+            return;
+        }
+    };
+    if embedded {
+        return;
+    }
+
+    log::warn!(
+        "Node {} stored without an embedding, flagging it for retry",
+        node_id
+    );
+    let flagged = match service.get_node(node_id).await {
+        Ok(Some(node)) => {
+            let metadata = mark_embedding_failed(node.metadata.as_ref());
+            store_node_metadata(state, service, node_id, metadata)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        Ok(None) => return,
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = flagged {
+        log::warn!("Failed to flag node {} for embedding retry: {}", node_id, e);
+    }
+}
+
+/// Length of the vectors the current embedding model produces
+async fn embedding_dimension(service: &NodeSpaceServiceImpl) -> Result<usize, AppError> {
+    service
+        .embedding_dimension()
+        .await
+        .map_err(|e| classify_service_error(&e, "Failed to determine embedding dimension"))
+}
+
+async fn process_image_file(
+    file_path: String,
+    roots: &[std::path::PathBuf],
+    state: &State<'_, AppState>,
+) -> Result<ImageData, AppError> {
+    let LoadedImage {
+        bytes: image_data,
+        image: img,
+        ..
+    } = load_allowed_image(&file_path, roots, state.config().await.max_image_bytes)?;
+    let file_size = image_data.len() as u64;
+
+    let exif_data = exif_info::extract_exif_data(&image_data);
+    let (width, height) = exif_info::oriented_dimensions(
+        img.width(),
+        img.height(),
+        exif_info::orientation(exif_data.as_ref()),
+    );
+
+    let filename = std::path::Path::new(&file_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let mime_type = mime_guess::from_path(&file_path)
+        .first_or_octet_stream()
+        .to_string();
+
+    // Neither the embedding model nor the webview can be relied on to read HEIF, so both
+    // are given a PNG conversion instead
+    let thumbnail_url = thumbnail_data_url(&img)?;
+
+    let (image_data, preview_mime_type) = if heif::is_heif(&image_data) {
+        (encode_png(&img)?, "image/png".to_string())
+    } else {
+        (image_data, mime_type.clone())
+    };
+
+    let service = &state.get_service().await?;
+    let generated = async {
+        let embedding = service
+            .generate_image_embedding(&image_data)
+            .await
+            .map_err(|e| classify_service_error(&e, "Failed to generate image embedding"))?;
+        let expected_dimension = embedding_dimension(service).await?;
+        check_generated_embedding(&embedding, expected_dimension)?;
+        Ok::<_, AppError>(embedding)
+    };
+    // The image is still worth importing without a vector; it is flagged for a retry
+    let (embeddings, embedding_failed) = match generated.await {
+        Ok(embedding) => (embedding, false),
+        Err(e) => {
+            log::warn!("Image {} imported without an embedding: {}", file_path, e);
+            (Vec::new(), true)
+        }
+    };
+
+    use base64::{engine::general_purpose, Engine as _};
+    let base64_data = general_purpose::STANDARD.encode(&image_data);
+    let blob_url = format!("data:{};base64,{}", preview_mime_type, base64_data);
+
+    let coordinates = exif_info::gps_coordinates(exif_data.as_ref());
+    let image_metadata = ImageMetadata {
+        filename,
+        mime_type,
+        file_size,
+        width,
+        height,
+        lat: coordinates.map(|(lat, _)| lat),
+        lng: coordinates.map(|(_, lng)| lng),
+        location: None,
+        exif_data,
+        ai_description: None,
+        created_at: chrono::Utc::now(),
+        embedding_failed,
+    };
+
+    let image_data = ImageData {
+        file_path,
+        metadata: image_metadata,
+        embeddings,
+        blob_url,
+        thumbnail_url,
+        dimensions: (width, height),
+    };
+
+    log::info!(
+        "Processed image file: {} ({}x{})",
+        image_data.metadata.filename,
+        width,
+        height
+    );
+    Ok(image_data)
+}
+
+/// Directories images may be read from on request of the frontend: the user's home and
+/// the directory holding the database and its imported assets
+fn allowed_file_roots(config: &AppConfig) -> Vec<std::path::PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(std::path::PathBuf::from);
+    let data_dir = config.database_path.parent().map(|p| p.to_path_buf());
+    home.into_iter().chain(data_dir).collect()
+}
+
+/// Canonicalize `path`, resolving `..` and symlinks, and require it to sit inside one of
+/// `roots`
+fn resolve_allowed_path(
+    path: &str,
+    roots: &[std::path::PathBuf],
+) -> Result<std::path::PathBuf, AppError> {
+    let resolved = std::fs::canonicalize(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound(format!("File {}", path)),
+        _ => AppError::InvalidInput(format!("Cannot resolve path {}: {}", path, e)),
+    })?;
+    let allowed = roots
+        .iter()
+        .filter_map(|root| std::fs::canonicalize(root).ok())
+        .any(|root| resolved.starts_with(root));
+    if !allowed {
+        return Err(AppError::PermissionDenied(format!(
+            "Path {} is outside the allowed directories",
+            path
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Read an image from disk as a full-resolution data URL. HEIF images are converted to
+/// PNG, as for previews.
+fn read_full_image(
+    file_path: &str,
+    roots: &[std::path::PathBuf],
+    max_bytes: u64,
+) -> Result<String, AppError> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let LoadedImage { path, bytes, image } = load_allowed_image(file_path, roots, max_bytes)?;
+    let (bytes, mime_type) = if heif::is_heif(&bytes) {
+        (encode_png(&image)?, "image/png".to_string())
+    } else {
+        let mime_type = mime_guess::from_path(&path).first_or_octet_stream();
+        (bytes, mime_type.to_string())
+    };
+
+    Ok(format!(
+        "data:{};base64,{}",
+        mime_type,
+        general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// An image file read from disk, with its bytes checked to decode as an image
+struct LoadedImage {
+    path: std::path::PathBuf,
+    bytes: Vec<u8>,
+    image: image::DynamicImage,
+}
+
+/// Read an image the frontend asked for. The path must resolve inside one of `roots`
+/// and the file must be non-empty, within `max_bytes` and actually decode as an image;
+/// the extension alone is not trusted.
+fn load_allowed_image(
+    file_path: &str,
+    roots: &[std::path::PathBuf],
+    max_bytes: u64,
+) -> Result<LoadedImage, AppError> {
+    if !is_image_file(file_path) {
+        return Err(AppError::InvalidInput(
+            "File is not a supported image format".to_string(),
+        ));
+    }
+    let path = resolve_allowed_path(file_path, roots)?;
+    let size = std::fs::metadata(&path)
+        .map_err(|e| AppError::Internal(format!("Failed to read file metadata: {}", e)))?
+        .len();
+    check_image_size(size, max_bytes)?;
+
+    let bytes = std::fs::read(&path)
+        .map_err(|e| AppError::Internal(format!("Failed to read image file: {}", e)))?;
+    let image = decode_image(&bytes).map_err(AppError::InvalidInput)?;
+    Ok(LoadedImage { path, bytes, image })
+}
+
+/// Decode any supported image, including HEIC/HEIF when the `heic` feature is enabled
+fn decode_image(image_bytes: &[u8]) -> Result<image::DynamicImage, String> {
+    if heif::is_heif(image_bytes) {
+        return heif::decode(image_bytes);
+    }
+    image::load_from_memory(image_bytes).map_err(|e| format!("Invalid image format: {}", e))
+}
+
+fn encode_png(img: &image::DynamicImage) -> Result<Vec<u8>, AppError> {
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut encoded, image::ImageFormat::Png)
+        .map_err(|e| AppError::Internal(format!("Failed to convert image to PNG: {}", e)))?;
+    Ok(encoded.into_inner())
+}
+
+/// Downscale an encoded image so its longest edge fits the thumbnail bound and return
+/// it as a JPEG data URL.
+fn create_thumbnail_data_url(image_bytes: &[u8]) -> Result<String, AppError> {
+    thumbnail_data_url(&decode_image(image_bytes).map_err(AppError::InvalidInput)?)
+}
+
+fn thumbnail_data_url(img: &image::DynamicImage) -> Result<String, AppError> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let thumbnail = image::DynamicImage::ImageRgb8(
+        img.thumbnail(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION)
+            .to_rgb8(),
+    );
+
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut encoded, image::ImageFormat::Jpeg)
+        .map_err(|e| AppError::Internal(format!("Failed to encode thumbnail: {}", e)))?;
+
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        general_purpose::STANDARD.encode(encoded.into_inner())
+    ))
+}
+
+/// Image nodes that don't have a stored thumbnail yet
+fn needs_thumbnail(node: &Node) -> bool {
+    render::is_image_node(node)
+        && node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("thumbnail_url"))
+            .and_then(|v| v.as_str())
+            .map_or(true, str::is_empty)
+}
+
+/// The image node's source file, if it is still present on disk
+fn existing_image_path(node: &Node) -> Option<std::path::PathBuf> {
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get("file_path"))
+        .and_then(|v| v.as_str())
+        .map(std::path::PathBuf::from)
+        .filter(|path| path.is_file())
+}
+
+fn check_image_size(size: u64, limit: u64) -> Result<(), AppError> {
+    if size == 0 {
+        return Err(AppError::InvalidInput("Image file is empty".to_string()));
+    }
+    if size > limit {
+        return Err(AppError::InvalidInput(format!(
+            "Image file is {} bytes, which exceeds the {} byte limit",
+            size, limit
+        )));
+    }
+    Ok(())
+}
+
+fn is_image_file(file_path: &str) -> bool {
+    let path = std::path::Path::new(file_path);
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        matches!(
+            extension.to_lowercase().as_str(),
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "heic" | "heif"
+        )
+    } else {
+        false
+    }
+}
+
+/// Check node metadata against a JSON-schema-like spec.
+///
+/// Supports the subset integrators need for custom node types: a top-level `required`
+/// list and per-field `properties` entries with an optional `type` and `enum`.
+fn validate_metadata_against_schema(
+    metadata: Option<&serde_json::Value>,
+    schema: &serde_json::Value,
+) -> Result<ValidationResult, AppError> {
+    let schema = schema
+        .as_object()
+        .ok_or_else(|| AppError::InvalidInput("Schema must be a JSON object".to_string()))?;
+
+    let empty = serde_json::Map::new();
+    let fields = metadata.and_then(|m| m.as_object()).unwrap_or(&empty);
+
+    let mut missing = Vec::new();
+    if let Some(required) = schema.get("required") {
+        let required = required.as_array().ok_or_else(|| {
+            AppError::InvalidInput("Schema 'required' must be an array".to_string())
+        })?;
+        for name in required.iter().filter_map(|v| v.as_str()) {
+            if !fields.contains_key(name) {
+                missing.push(name.to_string());
+            }
+        }
+    }
+
+    let mut invalid = Vec::new();
+    if let Some(properties) = schema.get("properties") {
+        let properties = properties.as_object().ok_or_else(|| {
+            AppError::InvalidInput("Schema 'properties' must be an object".to_string())
+        })?;
+        for (name, spec) in properties {
+            let Some(value) = fields.get(name) else {
+                continue;
+            };
+
+            if let Some(expected) = spec.get("type").and_then(|t| t.as_str()) {
+                if !json_type_matches(value, expected) {
+                    invalid.push(FieldIssue {
+                        field: name.clone(),
+                        reason: format!("expected type '{}'", expected),
+                    });
+                    continue;
+                }
+            }
+
+            if let Some(allowed) = spec.get("enum").and_then(|e| e.as_array()) {
+                if !allowed.contains(value) {
+                    invalid.push(FieldIssue {
+                        field: name.clone(),
+                        reason: format!("value {} is not one of the allowed values", value),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(ValidationResult {
+        valid: missing.is_empty() && invalid.is_empty(),
+        missing,
+        invalid,
+    })
+}
+
+fn json_type_matches(value: &serde_json::Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Check a supplied embedding can be stored alongside model-generated ones
+fn validate_embedding(embedding: &[f32], expected_dimension: usize) -> Result<(), AppError> {
+    if embedding.len() != expected_dimension {
+        return Err(AppError::InvalidInput(format!(
+            "Embedding has {} dimensions but the model produces {}",
+            embedding.len(),
+            expected_dimension
+        )));
+    }
+    if embedding.iter().any(|v| !v.is_finite()) {
+        return Err(AppError::InvalidInput(
+            "Embedding contains NaN or infinite values".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Accept the dimension the engine reports only if a sample embedding it produced has
+/// that length
+fn confirm_embedding_dimension(reported: usize, sample: &[f32]) -> Result<usize, AppError> {
+    if sample.len() != reported {
+        return Err(AppError::NlpEngine(format!(
+            "Engine reports {}-dimensional embeddings but produced {} values",
+            reported,
+            sample.len()
+        )));
+    }
+    Ok(reported)
+}
+
+/// Like `validate_embedding`, but also rejects the all-zero vector a model returns when
+/// it failed silently, since that would match nothing in similarity search.
+fn check_generated_embedding(embedding: &[f32], expected_dimension: usize) -> Result<(), AppError> {
+    validate_embedding(embedding, expected_dimension)?;
+    if embedding.iter().all(|v| *v == 0.0) {
+        return Err(AppError::NlpEngine(
+            "Model returned an all-zero embedding".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Score nodes by cosine similarity to `reference` and keep the best `limit`
+fn rank_by_similarity(
+    reference: &[f32],
+    embedded: Vec<(Node, Vec<f32>)>,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut scored: Vec<(Node, f32)> = embedded
+        .into_iter()
+        .map(|(node, vector)| {
+            let score = embeddings::cosine_similarity(reference, &vector);
+            (node, score)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(limit);
+
+    scored
+        .into_iter()
+        .map(|(node, score)| {
+            let snippet = create_search_snippet(&node);
+            SearchResult {
+                node,
+                score: score as f64,
+                snippet,
+            }
+        })
+        .collect()
+}
+
+/// `rank_by_similarity` over every node except `source_id`
+fn rank_similar_nodes(
+    source_id: &str,
+    reference: &[f32],
+    embedded: Vec<(Node, Vec<f32>)>,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let others = embedded
+        .into_iter()
+        .filter(|(node, _)| node.id.0 != source_id)
+        .collect();
+    rank_by_similarity(reference, others, limit)
+}
+
+/// Mean of the embeddings in `embedded`, or `None` when there are none
+fn embedded_centroid(embedded: &[(Node, Vec<f32>)]) -> Option<Vec<f32>> {
+    embeddings::centroid(embedded.iter().map(|(_, vector)| vector.as_slice()))
+}
+
+/// The node whose embedding lies nearest the centroid of all of them
+fn most_central_node(embedded: Vec<(Node, Vec<f32>)>) -> Option<Node> {
+    let centroid = embedded_centroid(&embedded)?;
+    let (nodes, vectors): (Vec<Node>, Vec<Vec<f32>>) = embedded.into_iter().unzip();
+    let index = embeddings::nearest_index(&vectors, &centroid)?;
+    nodes.into_iter().nth(index)
+}
+
+/// Run k-means from the given seeds and label each non-empty cluster with the member
+/// nearest its centroid.
+fn build_clusters(nodes: &[Node], vectors: &[Vec<f32>], seeds: Vec<Vec<f32>>) -> Vec<Cluster> {
+    let (assignments, centroids) = embeddings::kmeans(vectors, seeds, 50);
+
+    centroids
+        .iter()
+        .enumerate()
+        .filter_map(|(cluster, centroid)| {
+            let members: Vec<usize> = (0..nodes.len())
+                .filter(|i| assignments[*i] == cluster)
+                .collect();
+            let member_vectors: Vec<Vec<f32>> =
+                members.iter().map(|i| vectors[*i].clone()).collect();
+            let label_index = members[embeddings::nearest_index(&member_vectors, centroid)?];
+            let label_node = &nodes[label_index];
+
+            Some(Cluster {
+                label_node_id: label_node.id.0.clone(),
+                label: node_title(label_node).unwrap_or_default(),
+                member_ids: members.iter().map(|i| nodes[*i].id.0.clone()).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Check a batch before anything is written: ids must be present and unique, metadata must
+/// fit the size cap, and a sibling reference to another batch member must point backwards
+/// so sequential creation never sees a predecessor that doesn't exist yet.
+fn validate_batch_requests(
+    requests: &[NodeCreateRequest],
+    metadata_limit: usize,
+) -> Result<(), AppError> {
+    if requests.is_empty() {
+        return Err(AppError::InvalidInput("No nodes provided".to_string()));
+    }
+    if requests.len() > MAX_BATCH_CREATE {
+        return Err(AppError::InvalidInput(format!(
+            "Batch of {} nodes exceeds the limit of {}",
+            requests.len(),
+            MAX_BATCH_CREATE
+        )));
+    }
+
+    let batch_ids: HashMap<&str, usize> = requests
+        .iter()
+        .enumerate()
+        .map(|(index, r)| (r.node_id.as_str(), index))
+        .collect();
+    if batch_ids.len() != requests.len() {
+        return Err(AppError::InvalidInput(
+            "Duplicate node ids in batch".to_string(),
+        ));
+    }
+
+    for (index, request) in requests.iter().enumerate() {
+        if request.node_id.trim().is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "Node at position {} has an empty id",
+                index
+            )));
+        }
+        if let Some(metadata) = request.metadata.as_ref() {
+            check_metadata_size(metadata, metadata_limit)?;
+        }
+        let referenced = [&request.parent_id, &request.before_sibling_id];
+        for id in referenced.into_iter().flatten() {
+            if batch_ids.get(id.as_str()).is_some_and(|i| *i >= index) {
+                return Err(AppError::InvalidInput(format!(
+                    "Node {} references {}, which is not created before it in the batch",
+                    request.node_id, id
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check a planned bundle import before anything is written: every node's metadata must
+/// fit the size cap and every bundled image must be present in the bundle.
+fn validate_planned_imports(
+    planned: &[bundle::PlannedImport],
+    metadata_limit: usize,
+) -> Result<(), AppError> {
+    for plan in planned {
+        if let Some(metadata) = plan.metadata.as_ref() {
+            check_metadata_size(metadata, metadata_limit)?;
+        }
+        if let Some((source, _)) = plan.asset.as_ref() {
+            if !source.is_file() {
+                return Err(AppError::InvalidInput(format!(
+                    "Image {} of bundled node {} is missing from the bundle",
+                    source.display(),
+                    plan.original_id
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Metadata for a new task: not completed, with the due date and priority if given
+fn task_metadata(
+    due_date: Option<&str>,
+    priority: Option<&str>,
+) -> Result<serde_json::Value, AppError> {
+    let mut metadata = serde_json::Map::new();
+    metadata.insert(COMPLETED_KEY.to_string(), serde_json::Value::Bool(false));
+
+    if let Some(due_date) = due_date {
+        let due = parse_date(due_date, "due date")?;
+        metadata.insert(
+            DUE_DATE_KEY.to_string(),
+            serde_json::Value::String(due.format("%Y-%m-%d").to_string()),
+        );
+    }
+
+    if let Some(priority) = priority {
+        if !TASK_PRIORITIES.contains(&priority) {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid priority: {}. Expected one of low, medium, high",
+                priority
+            )));
+        }
+        metadata.insert(
+            PRIORITY_KEY.to_string(),
+            serde_json::Value::String(priority.to_string()),
+        );
+    }
+
+    Ok(serde_json::Value::Object(metadata))
+}
+
+/// The task nodes among `nodes` that match `filter`, sorted as `get_tasks` returns them
+fn select_tasks(nodes: Vec<Node>, filter: &TaskFilter) -> Result<Vec<Node>, AppError> {
+    let due_before = filter
+        .due_before
+        .as_deref()
+        .map(|d| parse_date(d, "due date"))
+        .transpose()?;
+    if let Some(priority) = filter.priority.as_deref() {
+        if !TASK_PRIORITIES.contains(&priority) {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid priority: {}. Expected one of low, medium, high",
+                priority
+            )));
+        }
+    }
+
+    let mut tasks: Vec<(Option<NaiveDate>, usize, Node)> = nodes
+        .into_iter()
+        .filter(|n| n.r#type == TASK_NODE_TYPE)
+        .filter(|n| {
+            filter
+                .completed
+                .map_or(true, |completed| render::is_task_completed(n) == completed)
+        })
+        .filter(|n| {
+            filter
+                .priority
+                .as_deref()
+                .map_or(true, |priority| task_priority(n) == Some(priority))
+        })
+        .filter_map(|n| {
+            let due = task_due_date(&n);
+            match due_before {
+                Some(bound) if !due.is_some_and(|d| d < bound) => None,
+                _ => Some((due, task_priority_rank(&n), n)),
+            }
+        })
+        .collect();
+
+    // `None` sorts first for Option, so compare "has no due date" before the date itself
+    tasks.sort_by_key(|(due, rank, _)| (due.is_none(), *due, *rank));
+    Ok(tasks.into_iter().map(|(_, _, n)| n).collect())
+}
+
+fn task_due_date(node: &Node) -> Option<NaiveDate> {
+    let due = node.metadata.as_ref()?.get(DUE_DATE_KEY)?.as_str()?;
+    NaiveDate::parse_from_str(due, "%Y-%m-%d").ok()
+}
+
+fn task_priority(node: &Node) -> Option<&str> {
+    node.metadata.as_ref()?.get(PRIORITY_KEY)?.as_str()
+}
+
+/// Position in `TASK_PRIORITIES`, so higher priorities sort first; unset or unknown last
+fn task_priority_rank(node: &Node) -> usize {
+    task_priority(node)
+        .and_then(|p| TASK_PRIORITIES.iter().position(|known| *known == p))
+        .unwrap_or(TASK_PRIORITIES.len())
+}
+
+fn parse_node_type(node_type: &str) -> NodeType {
+    match node_type {
+        "task" => NodeType::Task,
+        "image" => NodeType::Image,
+        "date" => NodeType::Date,
+        // The data store has no AI chat variant yet; these are stored as text and told
+        // apart by the `node_type` metadata marker (see `with_node_type_marker`)
+        AI_CHAT_NODE_TYPE => NodeType::Text,
+        _ => NodeType::Text,
+    }
+}
+
+/// Record node types the data store can't represent natively as a `node_type` metadata
+/// marker, so they survive a reload. Other types pass their metadata through unchanged.
+fn with_node_type_marker(
+    node_type: &str,
+    metadata: Option<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    if node_type != AI_CHAT_NODE_TYPE {
+        return metadata;
+    }
+
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            "node_type".to_string(),
+            serde_json::Value::String(AI_CHAT_NODE_TYPE.to_string()),
+        );
+    }
+    Some(metadata)
+}
+
+/// Creation requests copying a subtree with fresh ids, parents before children. The
+/// subtree's root becomes a top-level node.
+fn plan_duplicate(
+    subtree: Vec<Node>,
+    max_depth: usize,
+) -> Result<Vec<NodeCreateRequest>, AppError> {
+    let mut requests = Vec::with_capacity(subtree.len());
+    let within_limit = plan_duplicate_level(
+        hierarchy::build_outline(subtree),
+        None,
+        max_depth,
+        &mut requests,
+    );
+    if !within_limit {
+        return Err(AppError::InvalidInput(format!(
+            "Subtree is deeper than {} levels",
+            max_depth
+        )));
+    }
+    Ok(requests)
+}
+
+/// Returns false, leaving the plan incomplete, once the subtree runs deeper than
+/// `remaining_depth` levels
+fn plan_duplicate_level(
+    level: Vec<hierarchy::OutlineNode>,
+    parent_id: Option<String>,
+    remaining_depth: usize,
+    requests: &mut Vec<NodeCreateRequest>,
+) -> bool {
+    if level.is_empty() {
+        return true;
+    }
+    if remaining_depth == 0 {
+        return false;
+    }
+
+    let mut previous = None;
+    for hierarchy::OutlineNode { node, children } in level {
+        let node_id = NodeId::new().0;
+        let node_type = effective_node_type(&node).to_string();
+        requests.push(NodeCreateRequest {
+            node_id: node_id.clone(),
+            content: node.content.as_str().unwrap_or_default().to_string(),
+            parent_id: parent_id.clone(),
+            before_sibling_id: previous.replace(node_id.clone()),
+            node_type: Some(node_type),
+            metadata: node.metadata,
+        });
+        if !plan_duplicate_level(children, Some(node_id), remaining_depth - 1, requests) {
+            return false;
+        }
+    }
+    true
+}
+
+struct PlannedChild {
+    node_id: NodeId,
+    content: String,
+    before_sibling: Option<NodeId>,
+}
+
+/// Reject an empty batch, or one with a blank entry, before any child is created
+fn validate_child_contents(contents: &[String]) -> Result<(), AppError> {
+    if contents.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No child contents provided".to_string(),
+        ));
+    }
+    if let Some(index) = contents.iter().position(|c| c.trim().is_empty()) {
+        return Err(AppError::InvalidInput(format!(
+            "Child content at position {} is empty",
+            index
+        )));
+    }
+    Ok(())
+}
+
+/// Assign fresh IDs to `contents` and chain each entry after the previous one, starting
+/// after `first_predecessor`.
+fn plan_child_chain(contents: Vec<String>, first_predecessor: Option<NodeId>) -> Vec<PlannedChild> {
+    let mut previous = first_predecessor;
+    contents
+        .into_iter()
+        .map(|content| {
+            let node_id = NodeId::new();
+            let before_sibling = previous.replace(node_id.clone());
+            PlannedChild {
+                node_id,
+                content,
+                before_sibling,
+            }
+        })
+        .collect()
+}
+
+/// Blank, childless nodes left behind by aborted edits. Date and image nodes are never
+/// considered empty since their content lives elsewhere.
+fn find_empty_leaf_nodes(nodes: Vec<Node>) -> Vec<Node> {
+    let parents: std::collections::HashSet<String> = nodes
+        .iter()
+        .filter_map(|n| n.parent_id.as_ref().map(|p| p.0.clone()))
+        .collect();
+
+    nodes
+        .into_iter()
+        .filter(|n| n.r#type != "date" && !render::is_image_node(n))
+        .filter(|n| match &n.content {
+            serde_json::Value::String(text) => text.trim().is_empty(),
+            serde_json::Value::Null => true,
+            _ => false,
+        })
+        .filter(|n| !parents.contains(&n.id.0))
+        .collect()
+}
+
+/// Line breaks and tabs are legitimate outline content; every other control character is not
+fn is_disallowed_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\n' | '\r' | '\t')
+}
+
+fn has_encoding_issues(content: &str) -> bool {
+    content
+        .chars()
+        .any(|c| c == char::REPLACEMENT_CHARACTER || is_disallowed_control(c))
+}
+
+fn strip_control_chars(content: &str) -> String {
+    content
+        .chars()
+        .filter(|c| !is_disallowed_control(*c))
+        .collect()
+}
+
+struct PlannedReplacement {
+    node_id: NodeId,
+    new_content: String,
+    occurrences: usize,
+}
+
+/// Compute the content changes a literal find/replace would make, without writing anything.
+fn plan_replacements(nodes: &[Node], find: &str, replace: &str) -> Vec<PlannedReplacement> {
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let content = node.content.as_str()?;
+            let occurrences = content.matches(find).count();
+            (occurrences > 0).then(|| PlannedReplacement {
+                node_id: node.id.clone(),
+                new_content: content.replace(find, replace),
+                occurrences,
+            })
+        })
+        .collect()
+}
+
+fn with_sort_key(metadata: Option<&serde_json::Value>, key: f64) -> serde_json::Value {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(SORT_KEY.to_string(), serde_json::json!(key));
+    }
+    metadata
+}
+
+fn sort_key(node: &Node) -> Option<f64> {
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get(SORT_KEY))
+        .and_then(|v| v.as_f64())
+        .filter(|k| k.is_finite())
+}
+
+/// Order siblings by sort key, falling back to the sibling chain. The sort is stable, so
+/// equal keys and unkeyed nodes keep their chain order.
+fn sort_by_sort_key(siblings: Vec<Node>) -> Vec<Node> {
+    let mut ordered = hierarchy::order_siblings(siblings);
+    ordered.sort_by(|a, b| match (sort_key(a), sort_key(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    ordered
+}
+
+/// Add a top-level `collapsed` field to each node in `payload` so the UI can restore
+/// expand/collapse state from `collapsed_nodes` in the settings.
+fn annotate_collapsed_state(
+    payload: &mut serde_json::Value,
+    collapsed_nodes: &std::collections::BTreeMap<String, bool>,
+) {
+    match payload {
+        serde_json::Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| annotate_collapsed_state(item, collapsed_nodes)),
+        serde_json::Value::Object(object) => {
+            if object.contains_key("id") && object.contains_key("metadata") {
+                let collapsed = object
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .and_then(|id| collapsed_nodes.get(id).copied())
+                    .unwrap_or(false);
+                object.insert("collapsed".to_string(), serde_json::Value::Bool(collapsed));
+            }
+            object
+                .values_mut()
+                .for_each(|value| annotate_collapsed_state(value, collapsed_nodes));
+        }
+        _ => {}
+    }
+}
+
+fn node_tags(node: &Node) -> Vec<&str> {
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get("tags"))
+        .and_then(|t| t.as_array())
+        .map(|tags| tags.iter().filter_map(|t| t.as_str()).collect())
+        .unwrap_or_default()
+}
+
+/// The UTC calendar date of a node's `created_at` timestamp
+fn created_date(node: &Node) -> Option<NaiveDate> {
+    chrono::DateTime::parse_from_rfc3339(&node.created_at)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Utc).date_naive())
+}
+
+/// Tag usage among nodes created within the inclusive date range, most used first
+fn count_tags_in_range(nodes: &[Node], start: NaiveDate, end: NaiveDate) -> Vec<TagCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for node in nodes {
+        if !created_date(node).is_some_and(|d| d >= start && d <= end) {
+            continue;
+        }
+        for tag in node_tags(node) {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut counts: Vec<TagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| TagCount {
+            tag: tag.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    counts
+}
+
+/// A node's `updated_at` in milliseconds since the epoch, falling back to `created_at`.
+/// Unparseable timestamps count as version 0.
+fn node_version(node: &Node) -> u64 {
+    [&node.updated_at, &node.created_at]
+        .into_iter()
+        .find_map(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.timestamp_millis().max(0) as u64)
+        .unwrap_or(0)
+}
+
+/// Split a date's nodes into those changed after `since_version` and the IDs of all of
+/// them. Version 0 means nothing has been seen yet, so every node counts as changed. The
+/// returned version never goes below `since_version`, even when the newest node was the
+/// one deleted.
+fn date_delta(nodes: Vec<Node>, since_version: u64) -> DateDelta {
+    let mut delta = DateDelta {
+        version: since_version,
+        ..DateDelta::default()
+    };
+    for node in nodes {
+        let version = node_version(&node);
+        delta.version = delta.version.max(version);
+        delta.node_ids.push(node.id.0.clone());
+        if since_version == 0 || version > since_version {
+            delta.changed.push(node);
+        }
+    }
+    delta
+}
+
+/// Non-date nodes by `updated_at`, newest first, capped at `limit`. Nodes with an
+/// unparseable `updated_at` sort last.
+fn find_recent_nodes(nodes: Vec<Node>, limit: usize) -> Vec<Node> {
+    let mut recent: Vec<(Option<chrono::DateTime<chrono::Utc>>, Node)> = nodes
+        .into_iter()
+        .filter(|n| n.r#type != "date")
+        .map(|n| {
+            let updated = chrono::DateTime::parse_from_rfc3339(&n.updated_at)
+                .ok()
+                .map(|t| t.with_timezone(&chrono::Utc));
+            (updated, n)
+        })
+        .collect();
+    recent.sort_by(|a, b| b.0.cmp(&a.0));
+    recent.into_iter().take(limit).map(|(_, n)| n).collect()
+}
+
+/// Non-date nodes last updated at least `older_than_days` before `now`, oldest first.
+/// Nodes with an unparseable `updated_at` are skipped rather than treated as stale.
+fn find_stale_nodes(
+    nodes: Vec<Node>,
+    now: chrono::DateTime<chrono::Utc>,
+    older_than_days: u32,
+    limit: usize,
+) -> Vec<Node> {
+    let cutoff = now - chrono::Duration::days(i64::from(older_than_days));
+
+    let mut stale: Vec<(chrono::DateTime<chrono::Utc>, Node)> = nodes
+        .into_iter()
+        .filter(|n| n.r#type != "date")
+        .filter_map(|n| {
+            let updated = chrono::DateTime::parse_from_rfc3339(&n.updated_at)
+                .ok()?
+                .with_timezone(&chrono::Utc);
+            (updated <= cutoff).then_some((updated, n))
+        })
+        .collect();
+    stale.sort_by(|a, b| a.0.cmp(&b.0));
+    stale.into_iter().take(limit).map(|(_, n)| n).collect()
+}
+
+fn parse_reminder_time(value: &str) -> Result<chrono::DateTime<chrono::Utc>, AppError> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .map_err(|e| {
+            AppError::InvalidInput(format!(
+                "Invalid reminder time '{}': {}. Expected RFC3339",
+                value, e
+            ))
+        })
+}
+
+fn reminder_time(node: &Node) -> Option<chrono::DateTime<chrono::Utc>> {
+    let remind_at = node.metadata.as_ref()?.get(REMIND_AT_KEY)?.as_str()?;
+    parse_reminder_time(remind_at).ok()
+}
+
+fn is_reminder_dismissed(node: &Node) -> bool {
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get(REMINDER_DISMISSED_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn with_reminder(
+    metadata: Option<&serde_json::Value>,
+    remind_at: chrono::DateTime<chrono::Utc>,
+) -> serde_json::Value {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            REMIND_AT_KEY.to_string(),
+            serde_json::Value::String(remind_at.to_rfc3339()),
+        );
+        object.remove(REMINDER_DISMISSED_KEY);
+    }
+    metadata
+}
+
+/// Task metadata with `completed` flipped, stamping `completed_at` when the task becomes
+/// complete and removing it when reopened. Also returns the new completed state.
+fn with_completion_toggled(
+    metadata: Option<&serde_json::Value>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> (serde_json::Value, bool) {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    let completed = !metadata
+        .get(COMPLETED_KEY)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            COMPLETED_KEY.to_string(),
+            serde_json::Value::Bool(completed),
+        );
+        if completed {
+            object.insert(
+                COMPLETED_AT_KEY.to_string(),
+                serde_json::Value::String(now.to_rfc3339()),
+            );
+        } else {
+            object.remove(COMPLETED_AT_KEY);
+        }
+    }
+    (metadata, completed)
+}
+
+/// AI chat metadata with the answer fields replaced by `response`, in the shape the
+/// frontend stores them. The question and any other keys are left alone.
+fn with_regenerated_answer(
+    metadata: Option<&serde_json::Value>,
+    response: &QueryResponse,
+) -> serde_json::Value {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    let sources: Vec<serde_json::Value> = response
+        .sources
+        .iter()
+        .map(|source| {
+            serde_json::json!({
+                "node_id": source.node.id.0,
+                "score": source.score,
+                "snippet": source.snippet,
+                "created_at": source.node.created_at,
+            })
+        })
+        .collect();
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert("response".to_string(), response.answer.clone().into());
+        object.insert(
+            "node_sources".to_string(),
+            serde_json::Value::Array(sources),
+        );
+        object.insert("confidence".to_string(), response.confidence.into());
+        object.insert("overall_confidence".to_string(), response.confidence.into());
+    }
+    metadata
+}
+
+fn with_reminder_dismissed(metadata: Option<&serde_json::Value>) -> serde_json::Value {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            REMINDER_DISMISSED_KEY.to_string(),
+            serde_json::Value::Bool(true),
+        );
+    }
+    metadata
+}
+
+fn find_due_reminders(nodes: Vec<Node>, now: chrono::DateTime<chrono::Utc>) -> Vec<Node> {
+    let mut due: Vec<(chrono::DateTime<chrono::Utc>, Node)> = nodes
+        .into_iter()
+        .filter(|n| !is_reminder_dismissed(n))
+        .filter_map(|n| reminder_time(&n).filter(|t| *t <= now).map(|t| (t, n)))
+        .collect();
+    due.sort_by(|a, b| a.0.cmp(&b.0));
+    due.into_iter().map(|(_, n)| n).collect()
+}
+
+/// Trim tags and drop empties and duplicates, keeping first-seen order
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !normalized.iter().any(|existing| existing == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// The metadata each of `nodes` would store after tagging, leaving out nodes that already
+/// carry every tag
+fn plan_tag_updates(
+    nodes: impl IntoIterator<Item = Node>,
+    tags: &[String],
+) -> Vec<(NodeId, serde_json::Value)> {
+    nodes
+        .into_iter()
+        .filter_map(|node| apply_tags(node.metadata.as_ref(), tags).map(|m| (node.id, m)))
+        .collect()
+}
+
+fn tag_search_result(updates: &[(NodeId, serde_json::Value)], dry_run: bool) -> TagSearchResult {
+    let preview = if dry_run {
+        updates.iter().map(|(id, _)| id.0.clone()).collect()
+    } else {
+        Vec::new()
+    };
+    TagSearchResult {
+        count: updates.len(),
+        preview,
+        dry_run,
+    }
+}
+
+/// Merge `tags` into the metadata `tags` list, returning `None` when every tag is
+/// already present.
+fn apply_tags(metadata: Option<&serde_json::Value>, tags: &[String]) -> Option<serde_json::Value> {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    let object = metadata.as_object_mut()?;
+
+    let existing = object
+        .entry("tags")
+        .or_insert_with(|| serde_json::json!([]));
+    if !existing.is_array() {
+        *existing = serde_json::json!([]);
+    }
+    let list = existing.as_array_mut()?;
+
+    let mut changed = false;
+    for tag in tags {
+        if !list.iter().any(|v| v.as_str() == Some(tag.as_str())) {
+            list.push(serde_json::Value::String(tag.clone()));
+            changed = true;
+        }
+    }
+    changed.then_some(metadata)
+}
+
+/// The metadata to store for an `update_node_metadata` call. `update` must be a JSON
+/// object; when merging, stored metadata that isn't an object is treated as empty.
+fn apply_metadata_update(
+    existing: Option<&serde_json::Value>,
+    update: serde_json::Value,
+    merge: bool,
+) -> Result<serde_json::Value, AppError> {
+    let serde_json::Value::Object(update) = update else {
+        return Err(AppError::InvalidInput(
+            "Metadata must be a JSON object".to_string(),
+        ));
+    };
+    if !merge {
+        return Ok(serde_json::Value::Object(update));
+    }
+
+    let mut merged = existing
+        .and_then(|m| m.as_object())
+        .cloned()
+        .unwrap_or_default();
+    for (key, value) in update {
+        if value.is_null() {
+            merged.remove(&key);
+        } else {
+            merged.insert(key, value);
+        }
+    }
+    Ok(serde_json::Value::Object(merged))
+}
+
+/// Replace a node's metadata once it passes the configured `max_metadata_bytes`. Metadata
+/// updates go through here; paths that create nodes check the cap themselves before their
+/// first write.
+async fn store_node_metadata(
+    state: &AppState,
+    service: &NodeSpaceServiceImpl,
+    node_id: &NodeId,
+    metadata: serde_json::Value,
+) -> Result<(), AppError> {
+    check_metadata_size(&metadata, state.config().await.max_metadata_bytes)?;
+    service
+        .update_node_metadata(node_id, metadata)
+        .await
+        .map_err(|e| {
+            classify_service_error(
+                &e,
+                &format!("Failed to update metadata of node {}", node_id),
+            )
+        })?;
+    Ok(())
+}
+
+/// Reject metadata whose serialized JSON exceeds `limit` bytes, returning the size otherwise
+fn check_metadata_size(metadata: &serde_json::Value, limit: usize) -> Result<usize, AppError> {
+    let size = serde_json::to_vec(metadata)?.len();
+    if size > limit {
+        return Err(AppError::InvalidInput(format!(
+            "Metadata is {} bytes, which exceeds the {} byte limit",
+            size, limit
+        )));
+    }
+    Ok(size)
+}
+
+fn has_oversized_metadata(node: &Node, limit: usize) -> bool {
+    node.metadata
+        .as_ref()
+        .is_some_and(|m| check_metadata_size(m, limit).is_err())
+}
+
+fn needs_embedding_retry(node: &Node) -> bool {
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get(EMBEDDING_FAILED_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn mark_embedding_failed(metadata: Option<&serde_json::Value>) -> serde_json::Value {
+    let mut metadata = metadata.cloned().unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            EMBEDDING_FAILED_KEY.to_string(),
+            serde_json::Value::Bool(true),
+        );
+    }
+    metadata
+}
+
+fn clear_embedding_failure(metadata: Option<&serde_json::Value>) -> serde_json::Value {
+    let mut metadata = metadata.cloned().unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.remove(EMBEDDING_FAILED_KEY);
+    }
+    metadata
+}
+
+/// The calendar date a date node represents, read from its `date` metadata or content.
+fn date_node_date(node: &Node) -> Option<NaiveDate> {
+    if node.r#type != "date" {
+        return None;
+    }
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get("date"))
+        .and_then(|d| d.as_str())
+        .or_else(|| node.content.as_str())
+        .and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())
+}
+
+/// Whether `date` still holds any content node; a missing bound has nothing to check.
+async fn date_has_content(
+    service: &NodeSpaceServiceImpl,
+    date: Option<NaiveDate>,
+) -> Result<bool, AppError> {
+    let Some(date) = date else {
+        return Ok(true);
+    };
+    let nodes = service
+        .get_nodes_for_date(date)
+        .await
+        .map_err(|e| classify_store_error(&e, "Failed to get nodes for date"))?;
+    Ok(content_node_count(&nodes) > 0)
+}
+
+/// Extend cached date bounds so they cover `date`.
+fn widen_date_bounds(bounds: DateBounds, date: NaiveDate) -> DateBounds {
+    let (earliest, latest) = bounds;
+    (
+        Some(earliest.map_or(date, |d| d.min(date))),
+        Some(latest.map_or(date, |d| d.max(date))),
+    )
+}
+
+/// Earliest and latest dates that have at least one node rooted under them.
+fn compute_date_bounds(nodes: &[Node]) -> DateBounds {
+    let used_roots: std::collections::HashSet<&str> = nodes
+        .iter()
+        .filter_map(|n| n.root_id.as_ref().map(|r| r.0.as_str()))
+        .collect();
+
+    let dates: Vec<NaiveDate> = nodes
+        .iter()
+        .filter(|n| used_roots.contains(n.id.0.as_str()))
+        .filter_map(date_node_date)
+        .collect();
+
+    (dates.iter().min().copied(), dates.iter().max().copied())
+}
+
+/// Compare each node's stored `date` metadata against the date of its root date node
+fn find_date_mismatches(nodes: &[Node]) -> Vec<DateMismatch> {
+    let root_dates: HashMap<&str, NaiveDate> = nodes
+        .iter()
+        .filter_map(|n| date_node_date(n).map(|d| (n.id.0.as_str(), d)))
+        .collect();
+
+    nodes
+        .iter()
+        .filter(|n| n.r#type != "date")
+        .filter_map(|node| {
+            let root_id = node.root_id.as_ref()?;
+            let root_date = root_dates.get(root_id.0.as_str())?;
+            let stored = node.metadata.as_ref()?.get("date")?.as_str()?;
+
+            let matches = NaiveDate::parse_from_str(stored, "%Y-%m-%d")
+                .map(|d| d == *root_date)
+                .unwrap_or(false);
+            (!matches).then(|| DateMismatch {
+                node_id: node.id.0.clone(),
+                stored_date: stored.to_string(),
+                root_id: root_id.0.clone(),
+                root_date: root_date.format("%Y-%m-%d").to_string(),
+                repaired: false,
+            })
+        })
+        .collect()
+}
+
+fn with_date_context(metadata: Option<&serde_json::Value>, date: &str) -> serde_json::Value {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            "date".to_string(),
+            serde_json::Value::String(date.to_string()),
+        );
+    }
+    metadata
+}
+
+/// Turn nodes into graph vertices plus hierarchy and reference edges. Edges are only kept
+/// when both ends survive the `max_vertices` cap, which favours recently updated nodes.
+fn build_graph_snapshot(mut nodes: Vec<Node>, max_vertices: usize) -> GraphSnapshot {
+    let total_nodes = nodes.len();
+    let truncated = total_nodes > max_vertices;
+    if truncated {
+        // RFC3339 timestamps written by the store sort chronologically as strings
+        nodes.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        nodes.truncate(max_vertices);
+    }
+
+    let included: std::collections::HashSet<&str> = nodes.iter().map(|n| n.id.0.as_str()).collect();
+    let mut edges = Vec::new();
+    for node in &nodes {
+        let parent = node.parent_id.as_ref();
+        if let Some(parent) = parent.filter(|p| included.contains(p.0.as_str())) {
+            edges.push(GraphEdge {
+                source: parent.0.clone(),
+                target: node.id.0.clone(),
+                kind: GraphEdgeKind::Hierarchy,
+            });
+        }
+
+        let references = node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("references"))
+            .and_then(|r| r.as_array());
+        for target in references.into_iter().flatten().filter_map(|r| r.as_str()) {
+            if included.contains(target) && target != node.id.0 {
+                edges.push(GraphEdge {
+                    source: node.id.0.clone(),
+                    target: target.to_string(),
+                    kind: GraphEdgeKind::Reference,
+                });
+            }
+        }
+    }
+
+    let vertices = nodes
+        .iter()
+        .map(|n| GraphVertex {
+            id: n.id.0.clone(),
+            label: node_title(n).unwrap_or_default(),
+            node_type: n.r#type.clone(),
+        })
+        .collect();
+
+    GraphSnapshot {
+        vertices,
+        edges,
+        total_nodes,
+        truncated,
+    }
+}
+
+/// Serialize nodes as newline-delimited JSON, `batch_size` nodes per batch. Each batch is
+/// paired with the number of nodes it holds.
+fn ndjson_batches(nodes: &[Node], batch_size: usize) -> Result<Vec<(String, usize)>, AppError> {
+    nodes
+        .chunks(batch_size.max(1))
+        .map(|batch| {
+            let mut ndjson = String::new();
+            for node in batch {
+                ndjson.push_str(&serde_json::to_string(node)?);
+                ndjson.push('\n');
+            }
+            Ok((ndjson, batch.len()))
+        })
+        .collect()
+}
+
+fn compare_hierarchy(
+    node: &Node,
+    expected_parent_id: Option<String>,
+    expected_before_sibling: Option<String>,
+) -> ReconcileReport {
+    let stored_parent_id = node.parent_id.as_ref().map(|p| p.0.clone());
+    let stored_before_sibling = node.before_sibling.as_ref().map(|p| p.0.clone());
+
+    ReconcileReport {
+        node_id: node.id.0.clone(),
+        parent_matches: stored_parent_id == expected_parent_id,
+        before_sibling_matches: stored_before_sibling == expected_before_sibling,
+        stored_parent_id,
+        expected_parent_id,
+        stored_before_sibling,
+        expected_before_sibling,
+        repaired: false,
+    }
+}
+
+/// The title of a node is its first non-empty line of content.
+fn node_title(node: &Node) -> Option<String> {
+    node.content
+        .as_str()?
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| line.to_string())
+}
+
+/// Case-insensitive search for `phrase` that only matches on word boundaries, so a
+/// title like "Rust" doesn't match inside "Rusty" or "trust".
+fn contains_whole_phrase(haystack: &str, phrase: &str) -> bool {
+    let haystack = haystack.to_lowercase();
+    let phrase = phrase.trim().to_lowercase();
+    if phrase.is_empty() {
+        return false;
+    }
+
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    haystack.match_indices(&phrase).any(|(start, _)| {
+        let end = start + phrase.len();
+        let before_ok = haystack[..start]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !is_word_char(c));
+        let after_ok = haystack[end..]
+            .chars()
+            .next()
+            .map_or(true, |c| !is_word_char(c));
+        before_ok && after_ok
+    })
+}
+
+/// Add `target_id` to the `references` list, returning the updated metadata or `None`
+/// when the reference already exists.
+fn add_reference(
+    metadata: Option<&serde_json::Value>,
+    target_id: &str,
+) -> Option<serde_json::Value> {
+    let mut metadata = metadata
+        .filter(|m| m.is_object())
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    let object = metadata.as_object_mut()?;
+
+    let references = object
+        .entry("references")
+        .or_insert_with(|| serde_json::json!([]));
+    if !references.is_array() {
+        *references = serde_json::json!([]);
+    }
+    let list = references.as_array_mut()?;
+    if list.iter().any(|v| v.as_str() == Some(target_id)) {
+        return None;
+    }
+    list.push(serde_json::Value::String(target_id.to_string()));
+    Some(metadata)
+}
+
+/// Whether `hour` falls inside the inclusive `start..=end` window. A window whose start
+/// is after its end wraps past midnight (e.g. 22..=3).
+fn hour_in_window(hour: u32, start: u32, end: u32) -> bool {
+    if start <= end {
+        hour >= start && hour <= end
+    } else {
+        hour >= start || hour <= end
+    }
+}
+
+/// Keep nodes whose `created_at` hour, in the timestamp's own offset, is inside the window.
+fn filter_nodes_by_time_of_day(nodes: Vec<Node>, start_hour: u32, end_hour: u32) -> Vec<Node> {
+    use chrono::Timelike;
+
+    nodes
+        .into_iter()
+        .filter(|node| {
+            chrono::DateTime::parse_from_rfc3339(&node.created_at)
+                .map(|created| hour_in_window(created.hour(), start_hour, end_hour))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Validate a user-supplied image filename and resolve it next to the current file.
+///
+/// The new name must be a bare filename (no separators or parent references) and must
+/// keep the original extension; a name without an extension inherits the current one.
+fn validate_image_rename(
+    current_path: &str,
+    new_filename: &str,
+) -> Result<std::path::PathBuf, AppError> {
+    use std::path::{Component, Path};
+
+    let new_filename = new_filename.trim();
+    if new_filename.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Filename cannot be empty".to_string(),
+        ));
+    }
+
+    let mut components = Path::new(new_filename).components();
+    let is_plain_name = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !new_filename.contains(['/', '\\']);
+    if !is_plain_name {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid filename '{}': path separators and parent references are not allowed",
+            new_filename
+        )));
+    }
+
+    let current = Path::new(current_path);
+    let current_ext = current
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .ok_or_else(|| AppError::InvalidInput("Current image has no extension".to_string()))?;
+
+    let filename = match Path::new(new_filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some(ext) if ext.eq_ignore_ascii_case(current_ext) => new_filename.to_string(),
+        Some(ext) if is_image_file(new_filename) => {
+            return Err(AppError::InvalidInput(format!(
+                "Changing the image extension from '{}' to '{}' is not allowed",
+                current_ext, ext
+            )))
+        }
+        _ => format!("{}.{}", new_filename, current_ext),
+    };
+
+    let parent = current.parent().unwrap_or_else(|| Path::new(""));
+    Ok(parent.join(filename))
+}
+
+/// Characters of context kept on each side of the matched query term
+const SNIPPET_CONTEXT_CHARS: usize = 40;
+
+/// Wrapped around matched query terms in snippets so the UI can highlight them
+const HIGHLIGHT_MARKER: &str = "**";
+
+/// Longest passage shown whole as a source snippet; longer ones are trimmed around
+/// their first match
+const MAX_SOURCE_PASSAGE_CHARS: usize = 200;
+
+/// Question words too common to say why a source is relevant
+const QUESTION_STOP_WORDS: &[&str] = &[
+    "about", "and", "any", "are", "can", "could", "did", "does", "for", "from", "had", "has",
+    "have", "how", "into", "not", "our", "should", "tell", "than", "that", "the", "their", "them",
+    "then", "there", "they", "this", "was", "were", "what", "when", "where", "which", "who",
+    "whom", "whose", "why", "will", "with", "would", "you", "your",
+];
+
+/// Byte range of the first case-insensitive occurrence of `needle` in `haystack`
+fn find_case_insensitive(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    let needle: Vec<char> = needle.chars().collect();
+    if needle.is_empty() {
+        return None;
+    }
+
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut rest = haystack[start..].char_indices();
+        for expected in &needle {
+            let (_, c) = rest.next()?;
+            if !c.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
+        }
+        let end = rest
+            .next()
+            .map_or(haystack.len(), |(offset, _)| start + offset);
+        Some((start, end))
+    })
+}
+
+/// The first `limit` results whose node is in `subtree`, in their original order
+fn restrict_to_subtree(
+    results: Vec<SearchResult>,
+    subtree: &[Node],
+    limit: usize,
+) -> Vec<SearchResult> {
+    let members: std::collections::HashSet<&str> =
+        subtree.iter().map(|n| n.id.0.as_str()).collect();
+    results
+        .into_iter()
+        .filter(|r| members.contains(r.node.id.0.as_str()))
+        .take(limit)
+        .collect()
+}
+
+/// Merge semantic and keyword results by node ID. Each list's scores are scaled to 0–1 by
+/// its best score, then blended as `semantic_weight * semantic + (1 - semantic_weight) *
+/// keyword`, with a missing side counting as 0. Returns the top `limit` by blended score.
+fn blend_search_results(
+    semantic: Vec<SearchResult>,
+    keyword: Vec<SearchResult>,
+    semantic_weight: f64,
+    limit: usize,
+) -> Vec<SearchResult> {
+    fn normalized(results: Vec<SearchResult>) -> impl Iterator<Item = (SearchResult, f64)> {
+        let best = results.iter().map(|r| r.score).fold(0.0, f64::max);
+        results.into_iter().map(move |r| {
+            let score = if best > 0.0 {
+                (r.score / best).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (r, score)
+        })
+    }
+
+    let mut blended: Vec<SearchResult> = Vec::new();
+    let mut index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let sources = normalized(semantic)
+        .map(|(r, s)| (r, semantic_weight * s))
+        .chain(normalized(keyword).map(|(r, s)| (r, (1.0 - semantic_weight) * s)));
+    for (result, weighted) in sources {
+        match index.get(&result.node.id.0) {
+            Some(&i) => blended[i].score += weighted,
+            None => {
+                index.insert(result.node.id.0.clone(), blended.len());
+                blended.push(SearchResult {
+                    score: weighted,
+                    ..result
+                });
+            }
+        }
+    }
+
+    blended.sort_by(|a, b| b.score.total_cmp(&a.score));
+    blended.truncate(limit);
+    blended
+}
+
+/// Relevance of a literal match of `query` in `content`, or None when it doesn't occur.
+/// A match of the whole (trimmed) content scores 1.0; other matches score below that,
+/// higher the earlier the first match and the more often the query occurs.
+fn text_match_score(content: &str, query: &str) -> Option<f64> {
+    let content = content.trim();
+    let (first, mut end) = find_case_insensitive(content, query)?;
+    if first == 0 && end == content.len() {
+        return Some(1.0);
+    }
+
+    let mut occurrences = 1;
+    while let Some((_, next_end)) = find_case_insensitive(&content[end..], query) {
+        end += next_end;
+        occurrences += 1;
+    }
+
+    let position = 1.0 - first as f64 / content.len() as f64;
+    let frequency = 1.0 - 1.0 / occurrences as f64;
+    Some(0.5 + 0.25 * position + 0.2 * frequency)
+}
+
+/// Nodes containing `query`, best match first (ties keep store order), capped at `limit`
+fn rank_text_matches(nodes: Vec<Node>, query: &str, limit: usize) -> Vec<SearchResult> {
+    let query = query.trim();
+    let mut results: Vec<SearchResult> = nodes
+        .into_iter()
+        .filter_map(|node| {
+            let score = text_match_score(node.content.as_str()?, query)?;
+            let snippet = create_query_snippet(&node, query);
+            Some(SearchResult {
+                node,
+                score,
+                snippet,
+            })
+        })
+        .collect();
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(limit);
+    results
+}
+
+/// The earliest match of any term in `text`, preferring the longest match at that position
+fn first_term_match(text: &str, terms: &[&str]) -> Option<(usize, usize)> {
+    terms
+        .iter()
+        .filter_map(|term| find_case_insensitive(text, term))
+        .min_by_key(|&(start, end)| (start, std::cmp::Reverse(end)))
+}
+
+/// Wrap every non-overlapping term match in `text` with `HIGHLIGHT_MARKER`
+fn highlight_terms(text: &str, terms: &[&str]) -> String {
+    let mut highlighted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end)) = first_term_match(rest, terms) {
+        highlighted.push_str(&rest[..start]);
+        highlighted.push_str(HIGHLIGHT_MARKER);
+        highlighted.push_str(&rest[start..end]);
+        highlighted.push_str(HIGHLIGHT_MARKER);
+        rest = &rest[end..];
+    }
+    highlighted.push_str(rest);
+    highlighted
+}
+
+/// A snippet centered on the earliest match of the whole query or any of its terms, with
+/// the matches inside the window highlighted. Falls back to the plain leading snippet
+/// when nothing matches literally, as for purely semantic hits.
+fn create_query_snippet(node: &Node, query: &str) -> String {
+    let Some(content) = node.content.as_str() else {
+        return create_search_snippet(node);
+    };
+    let terms: Vec<&str> = std::iter::once(query.trim())
+        .chain(query.split_whitespace())
+        .collect();
+    let Some((start, end)) = first_term_match(content, &terms) else {
+        return create_search_snippet(node);
+    };
+
+    let (window_start, window_end) = match_window(content, start, end);
+    highlighted_excerpt(content, window_start, window_end, &terms)
+}
+
+/// `text[start..end]` widened by `SNIPPET_CONTEXT_CHARS` on each side, as a byte range
+fn match_window(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let window_start = text[..start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    let window_end = text[end..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT_CHARS)
+        .map_or(text.len(), |(i, _)| end + i);
+    (window_start, window_end)
+}
+
+/// `content[start..end]` with its term matches highlighted, marked with ellipses where
+/// the excerpt cuts the content short
+fn highlighted_excerpt(content: &str, start: usize, end: usize, terms: &[&str]) -> String {
+    format!(
+        "{}{}{}",
+        if start > 0 { "..." } else { "" },
+        highlight_terms(&content[start..end], terms),
+        if end < content.len() { "..." } else { "" }
+    )
+}
+
+/// A source snippet showing why the node answers `question`: the sentence or line sharing
+/// the most distinct significant words with it, trimmed around its first match when
+/// long. Falls back to the leading snippet when no passage shares any.
+fn create_source_snippet(node: &Node, question: &str) -> String {
+    let Some(content) = node.content.as_str() else {
+        return create_search_snippet(node);
+    };
+    let terms = significant_terms(question);
+    let terms: Vec<&str> = terms.iter().map(|t| t.as_str()).collect();
+
+    let best = passage_ranges(content)
+        .into_iter()
+        .map(|(start, end)| {
+            let passage = &content[start..end];
+            let overlap = terms
+                .iter()
+                .filter(|term| find_case_insensitive(passage, term).is_some())
+                .count();
+            (overlap, start, end)
+        })
+        .filter(|(overlap, _, _)| *overlap > 0)
+        .max_by_key(|&(overlap, start, _)| (overlap, std::cmp::Reverse(start)));
+    let Some((_, start, end)) = best else {
+        return create_search_snippet(node);
+    };
+
+    let passage = &content[start..end];
+    let (start, end) = match first_term_match(passage, &terms) {
+        Some((match_start, match_end)) if passage.chars().count() > MAX_SOURCE_PASSAGE_CHARS => {
+            let (window_start, window_end) = match_window(passage, match_start, match_end);
+            (start + window_start, start + window_end)
+        }
+        _ => (start, end),
+    };
+    highlighted_excerpt(content, start, end, &terms)
+}
+
+/// Lowercased words of `text` worth matching on: at least three characters and not a
+/// stop word, without duplicates
+fn significant_terms(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.chars().count() >= 3
+            && !QUESTION_STOP_WORDS.contains(&word.as_str())
+            && !terms.contains(&word)
+        {
+            terms.push(word);
+        }
+    }
+    terms
+}
+
+/// Byte ranges of the sentences and lines in `content`, trimmed of surrounding whitespace
+fn passage_ranges(content: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut push_trimmed = |start: usize, end: usize| {
+        let passage = &content[start..end];
+        let trimmed = passage.trim();
+        if !trimmed.is_empty() {
+            let lead = passage.len() - passage.trim_start().len();
+            ranges.push((start + lead, start + lead + trimmed.len()));
+        }
+    };
+
+    let mut start = 0;
+    let mut chars = content.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let boundary = match c {
+            '\n' => Some(i),
+            '.' | '!' | '?' => chars
+                .peek()
+                .filter(|(_, next)| next.is_whitespace())
+                .map(|_| i + c.len_utf8()),
+            _ => None,
+        };
+        if let Some(end) = boundary {
+            push_trimmed(start, end);
+            start = end;
+        }
+    }
+    push_trimmed(start, content.len());
+    ranges
+}
+
+/// The type a node presents to the user. AI chat and image nodes may be stored under a
+/// generic type, so their metadata markers take precedence.
+fn effective_node_type(node: &Node) -> &str {
+    if render::is_ai_chat_node(node) {
+        AI_CHAT_NODE_TYPE
+    } else if render::is_image_node(node) {
+        "image"
+    } else {
+        &node.r#type
+    }
+}
+
+/// Keep results whose node type is listed; `None` or an empty list keeps everything
+fn filter_by_node_types(
+    results: Vec<SearchResult>,
+    node_types: Option<&[String]>,
+) -> Vec<SearchResult> {
+    match node_types {
+        Some(types) if !types.is_empty() => results
+            .into_iter()
+            .filter(|r| {
+                let node_type = effective_node_type(&r.node);
+                types.iter().any(|t| t == node_type)
+            })
+            .collect(),
+        _ => results,
+    }
+}
+
+/// Parse optional inclusive YYYY-MM-DD bounds; a missing bound leaves that side open
+fn parse_date_range(
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<(Option<NaiveDate>, Option<NaiveDate>), AppError> {
+    let from = from.map(|d| parse_date(d, "start date")).transpose()?;
+    let to = to.map(|d| parse_date(d, "end date")).transpose()?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(AppError::InvalidInput(
+                "Start date must not be after end date".to_string(),
+            ));
+        }
+    }
+    Ok((from, to))
+}
+
+/// Parse a YYYY-MM-DD date, naming it as `what` if it is malformed
+fn parse_date(value: &str, what: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|e| {
+        AppError::InvalidInput(format!("Invalid {}: {}. Expected YYYY-MM-DD", what, e))
+    })
+}
+
+/// Every date in the inclusive `from`..=`to` range, which may span at most
+/// `MAX_COUNT_RANGE_DAYS`
+fn count_range_days(from: &str, to: &str) -> Result<Vec<NaiveDate>, AppError> {
+    let (Some(from), Some(to)) = parse_date_range(Some(from), Some(to))? else {
+        unreachable!("both bounds were given");
+    };
+    if (to - from).num_days() >= MAX_COUNT_RANGE_DAYS {
+        return Err(AppError::InvalidInput(format!(
+            "Date range cannot exceed {} days",
+            MAX_COUNT_RANGE_DAYS
+        )));
+    }
+    Ok(from.iter_days().take_while(|d| *d <= to).collect())
+}
+
+fn content_node_count(nodes: &[Node]) -> usize {
+    nodes.iter().filter(|n| n.r#type != "date").count()
+}
+
+fn tally_date_counts(
+    per_day: Vec<(NaiveDate, Vec<Node>)>,
+) -> std::collections::HashMap<String, usize> {
+    per_day
+        .into_iter()
+        .map(|(date, nodes)| {
+            (
+                date.format("%Y-%m-%d").to_string(),
+                content_node_count(&nodes),
+            )
+        })
+        .collect()
+}
+
+/// Keep results created within the inclusive range. Nodes with an unparseable
+/// `created_at` are dropped whenever either bound is set.
+fn filter_by_created_range(
+    results: Vec<SearchResult>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Vec<SearchResult> {
+    if from.is_none() && to.is_none() {
+        return results;
+    }
+    results
+        .into_iter()
+        .filter(|r| {
+            created_date(&r.node).is_some_and(|d| {
+                from.map_or(true, |from| d >= from) && to.map_or(true, |to| d <= to)
+            })
+        })
+        .collect()
+}
+
+fn validate_search_window(offset: usize, limit: usize) -> Result<(), AppError> {
+    if offset.saturating_add(limit) > MAX_SEARCH_WINDOW {
+        return Err(AppError::InvalidInput(format!(
+            "Offset plus limit cannot exceed {}",
+            MAX_SEARCH_WINDOW
+        )));
+    }
+    Ok(())
+}
+
+fn paginate_results(results: Vec<SearchResult>, offset: usize, limit: usize) -> SearchPage {
+    let total_available = results.len();
+    SearchPage {
+        results: results.into_iter().skip(offset).take(limit).collect(),
+        offset,
+        total_available,
+    }
+}
+
+fn create_search_snippet(node: &Node) -> String {
+    let question = node
+        .metadata
+        .as_ref()
+        .filter(|_| render::is_ai_chat_node(node))
+        .and_then(|m| m.get("question"))
+        .and_then(|q| q.as_str());
+    if let Some(question) = question {
+        truncate_snippet(question)
+    } else if let Some(content_str) = node.content.as_str() {
+        truncate_snippet(content_str)
+    } else if let Some(metadata) = node.metadata.as_ref().and_then(|m| m.as_object()) {
+        if let Some(node_type) = metadata.get("node_type").and_then(|v| v.as_str()) {
+            match node_type {
+                "image" => {
+                    let filename = metadata
+                        .get("filename")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("image");
+                    format!("Image: {}", filename)
+                }
+                _ => "...".to_string(),
+            }
+        } else {
+            "...".to_string()
+        }
+    } else {
+        "...".to_string()
+    }
+}
+
+/// The first 100 characters of `text`, with an ellipsis if any were cut. Counts
+/// characters rather than bytes so multibyte text is never split mid-character.
+fn truncate_snippet(text: &str) -> String {
+    match text.char_indices().nth(100) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Declares the invoke handler and records the names it registers, so
+/// `verify_command_registry` can compare them against `api::COMMANDS`.
+macro_rules! register_commands {
+    ($($command:ident),* $(,)?) => {
+        const REGISTERED_COMMANDS: &[&str] = &[$(stringify!($command)),*];
+
+        fn invoke_handler()
+        -> impl Fn(tauri::ipc::Invoke<tauri::Wry>) -> bool + Send + Sync + 'static {
+            tauri::generate_handler![$($command),*]
+        }
+    };
+}
+
+register_commands![
+    greet,
+    create_knowledge_node,
+    update_node,
+    process_query,
+    semantic_search,
+    get_nodes_for_date,
+    update_node_content,
+    update_node_structure,
+    delete_node,
+    create_node_for_date,
+    create_node_for_date_with_id,
+    get_today_date,
+    upsert_node,
+    create_image_node,
+    process_dropped_files,
+    multimodal_search,
+    rename_image_file,
+    get_nodes_by_time_of_day,
+    validate_node_metadata,
+    autolink_mentions,
+    get_date_bounds,
+    retry_failed_embeddings,
+    render_date_html,
+    replace_in_subtree,
+    detect_sibling_conflicts,
+    backfill_thumbnails,
+    cluster_nodes,
+    set_default_model,
+    get_model_limits,
+    set_node_collapsed,
+    tag_search_results,
+    verify_node_dates,
+    generate_text,
+    get_acceleration_info,
+    create_children,
+    get_subtree_text,
+    find_empty_nodes,
+    get_database_version,
+    migrate_database,
+    rank_nodes_by_text,
+    find_oversized_metadata,
+    export_date_conversations,
+    get_tag_counts_for_range,
+    create_node_with_embedding,
+    get_api_info,
+    get_stale_nodes,
+    set_node_reminder,
+    get_due_reminders,
+    dismiss_reminder,
+    get_node_depth,
+    find_encoding_issues,
+    process_query_stream,
+    create_nodes_for_date_batch,
+    get_graph_snapshot,
+    resnippet,
+    verify_command_registry,
+    get_subtree_centroid,
+    get_representative_node,
+    stream_export_range,
+    set_node_sort_key,
+    get_children_sorted_by_key,
+    delete_nodes_batch,
+    get_latency_percentiles,
+    get_service_status,
+    reconcile_node,
+    export_subtree_bundle,
+    import_subtree_bundle,
+    move_node,
+    export_date_as_markdown,
+    import_markdown,
+    duplicate_node,
+    get_node_ancestors,
+    get_node_children,
+    reconfigure_service,
+    get_full_image,
+    update_node_metadata,
+    toggle_task_completion,
+    text_search,
+    hybrid_search,
+    cancel_operation,
+    get_recent_nodes,
+    count_nodes_for_date,
+    count_nodes_for_range,
+    regenerate_ai_chat,
+    find_similar_nodes,
+    list_available_models,
+    set_active_model,
+    get_log_file_path,
+    open_logs_directory,
+    get_nodes_for_date_since,
+    search_in_subtree,
+    get_embedding_dimension,
+    create_task_node,
+    get_tasks,
+];
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    if let Err(e) = init_logging(&AppConfig::new()) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+
+    log_startup();
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_opener::init())
+        .manage(AppState::default())
+        .setup(|app| {
+            log_service_init("Application State");
+            match app.path().app_data_dir() {
+                Ok(data_dir) => tauri::async_runtime::block_on(async {
+                    let state = app.state::<AppState>();
+                    state.config.lock().await.use_app_data_dir(&data_dir);
+                }),
+                Err(e) => log::warn!("Failed to resolve app data directory: {}", e),
+            }
+            log_service_ready("Application State");
+
+            log::info!("NodeSpace Desktop initialized");
+            Ok(())
+        })
+        .on_window_event(|_window, event| {
+            if let tauri::WindowEvent::CloseRequested { .. } = event {
+                log_shutdown();
+            }
+        })
+        .invoke_handler(invoke_handler())
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
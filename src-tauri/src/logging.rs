@@ -1,26 +1,96 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
 use log::info;
 
-/// Initialize logging for the application
-pub fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
+use crate::config::AppConfig;
+
+/// Warnings and errors are written here, inside the logs directory
+pub const LOG_FILE_NAME: &str = "nodespace.log";
+
+/// Set to `json` to log one JSON object per line instead of plain text
+pub const LOG_FORMAT_ENV: &str = "NODESPACE_LOG_FORMAT";
+
+/// How each log record is written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `2025-06-30 12:00:00.000[target][INFO] message`, for reading in a terminal
+    Text,
+    /// `{"timestamp", "target", "level", "message"}` per line, for log aggregators
+    Json,
+}
+
+impl LogFormat {
+    /// The format named by `NODESPACE_LOG_FORMAT`; plain text unless it is `json`
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var(LOG_FORMAT_ENV).ok().as_deref())
+    }
+
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some(value) if value.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+
+    pub fn format_line(
+        self,
+        timestamp: chrono::DateTime<chrono::Utc>,
+        target: &str,
+        level: log::Level,
+        message: &str,
+    ) -> String {
+        match self {
+            Self::Text => format!(
+                "{}[{}][{}] {}",
+                timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+                target,
+                level,
+                message
+            ),
+            Self::Json => serde_json::json!({
+                "timestamp": timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                "target": target,
+                "level": level.as_str(),
+                "message": message,
+            })
+            .to_string(),
+        }
+    }
+}
+
+/// The `logs` directory under the working directory, created if it doesn't exist yet
+pub fn ensure_logs_directory() -> std::io::Result<PathBuf> {
+    let logs_dir = std::env::current_dir()?.join("logs");
+    std::fs::create_dir_all(&logs_dir)?;
+    Ok(logs_dir)
+}
+
+/// Initialize logging for the application. `nodespace.log` is rotated according to the
+/// config's `log_max_bytes` and `log_archives`.
+pub fn init_logging(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let log_format = LogFormat::from_env();
     let log_level = if cfg!(debug_assertions) {
         log::LevelFilter::Debug
     } else {
         log::LevelFilter::Info
     };
 
-    // Create logs directory if it doesn't exist
-    let logs_dir = std::env::current_dir()?.join("logs");
-    std::fs::create_dir_all(&logs_dir)?;
+    let logs_dir = ensure_logs_directory()?;
 
     // Configure fern for structured logging
     fern::Dispatch::new()
-        .format(|out, message, record| {
+        .format(move |out, message, record| {
             out.finish(format_args!(
-                "{}[{}][{}] {}",
-                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-                record.target(),
-                record.level(),
-                message
+                "{}",
+                log_format.format_line(
+                    chrono::Utc::now(),
+                    record.target(),
+                    record.level(),
+                    &message.to_string(),
+                )
             ))
         })
         .level(log_level)
@@ -52,7 +122,11 @@ pub fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
         .chain(
             fern::Dispatch::new()
                 .level(log::LevelFilter::Warn)
-                .chain(fern::log_file(logs_dir.join("nodespace.log"))?),
+                .chain(Box::new(RotatingFile::open(
+                    logs_dir.join(LOG_FILE_NAME),
+                    config.log_max_bytes,
+                    config.log_archives,
+                )?) as Box<dyn Write + Send>),
         )
         .apply()?;
 
@@ -60,6 +134,100 @@ pub fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// A log file that is rotated once it grows past `max_bytes`: the active file becomes
+/// `<name>.1`, existing archives shift up by one, and anything beyond `max_archives` is
+/// deleted. The size is checked on flush, which fern does after every record, so records
+/// are never split across files.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_archives: usize,
+    file: Option<File>,
+    written: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_bytes: u64, max_archives: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            max_archives,
+            file: Some(file),
+            written,
+        })
+    }
+
+    fn archive_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", index));
+        self.path.with_file_name(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        // Close the active file first; Windows can't rename a file that is still open
+        self.file = None;
+
+        if self.max_archives > 0 {
+            remove_if_exists(&self.archive_path(self.max_archives))?;
+            for index in (1..self.max_archives).rev() {
+                let archive = self.archive_path(index);
+                if archive.exists() {
+                    std::fs::rename(&archive, self.archive_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.archive_path(1))?;
+        }
+
+        self.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)?,
+        );
+        self.written = 0;
+        Ok(())
+    }
+
+    fn file(&mut self) -> std::io::Result<&mut File> {
+        if self.file.is_none() {
+            // A failed rotation left no file open; carry on appending to the active path
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.written = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.file()?.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file()?.flush()?;
+        if self.written > self.max_bytes {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
 /// Log application startup
 pub fn log_startup() {
     info!("NodeSpace Desktop Application starting...");
@@ -92,8 +260,46 @@ pub fn log_command(command_name: &str, params: &str) {
     );
 }
 
+/// Log a Tauri command like `log_command` and time it. The returned guard logs how long
+/// the command took when it is dropped, so bind it for the whole command body:
+/// `let _timer = log_command_timed(...)`.
+pub fn log_command_timed(command_name: &'static str, params: &str) -> CommandTimer {
+    log_command(command_name, params);
+    CommandTimer::with_reporter(command_name, log_command_duration)
+}
+
+/// Reports a command's elapsed time when dropped; see `log_command_timed`
+pub struct CommandTimer {
+    command_name: &'static str,
+    started: Instant,
+    report: fn(&str, Duration),
+}
+
+impl CommandTimer {
+    pub(crate) fn with_reporter(command_name: &'static str, report: fn(&str, Duration)) -> Self {
+        Self {
+            command_name,
+            started: Instant::now(),
+            report,
+        }
+    }
+}
+
+impl Drop for CommandTimer {
+    fn drop(&mut self) {
+        (self.report)(self.command_name, self.started.elapsed());
+    }
+}
+
+fn log_command_duration(command_name: &str, elapsed: Duration) {
+    info!(
+        "Command {} completed in {:.1}ms",
+        command_name,
+        elapsed.as_secs_f64() * 1000.0
+    );
+}
+
 /// Log application shutdown
 pub fn log_shutdown() {
     info!("NodeSpace Desktop Application shutting down...");
 }
-
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use nodespace_core_types::NodeId;
+
+use crate::NodeCreateRequest;
+
+/// Indentation assumed per outline level when no bullet is nested
+const DEFAULT_INDENT_WIDTH: usize = 2;
+
+/// Columns a tab counts for when measuring indentation
+const TAB_WIDTH: usize = 4;
+
+struct OutlineLine<'a> {
+    indent: usize,
+    text: &'a str,
+    bulleted: bool,
+}
+
+/// Parse a pasted Markdown outline into node creation requests, in document order.
+///
+/// Bullets (`-`, `*`, `+`) nest by indentation, rounded to the nearest level since pasted
+/// lists are rarely consistent, and never more than one level deeper than the previous
+/// item. `[ ]`/`[x]` checkboxes become task nodes. Blank lines are skipped; any other
+/// line continues the content of the item before it.
+pub fn parse_markdown_outline(markdown: &str) -> Vec<NodeCreateRequest> {
+    let lines: Vec<OutlineLine> = markdown
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_line)
+        .collect();
+
+    let bullet_indents = || lines.iter().filter(|l| l.bulleted).map(|l| l.indent);
+    let base_indent = bullet_indents().min().unwrap_or(0);
+    let indent_width = bullet_indents()
+        .map(|indent| indent - base_indent)
+        .filter(|indent| *indent > 0)
+        .min()
+        .unwrap_or(DEFAULT_INDENT_WIDTH);
+
+    let mut requests: Vec<NodeCreateRequest> = Vec::new();
+    // Ids of the most recent item at each level of the current branch
+    let mut branch: Vec<String> = Vec::new();
+    // Last item created under each parent, so the next one can follow it
+    let mut last_child: HashMap<Option<String>, String> = HashMap::new();
+
+    for line in lines {
+        if !line.bulleted {
+            if let Some(previous) = requests.last_mut() {
+                previous.content.push('\n');
+                previous.content.push_str(line.text);
+                continue;
+            }
+        }
+
+        let relative = line.indent.saturating_sub(base_indent);
+        let level = ((relative + indent_width / 2) / indent_width).min(branch.len());
+        branch.truncate(level);
+
+        let (node_type, metadata, content) = match checkbox(line.text) {
+            Some((completed, rest)) => (
+                Some("task".to_string()),
+                Some(serde_json::json!({ "completed": completed })),
+                rest,
+            ),
+            None => (None, None, line.text),
+        };
+
+        let node_id = NodeId::new().0;
+        let parent_id = branch.last().cloned();
+        let before_sibling_id = last_child.insert(parent_id.clone(), node_id.clone());
+        requests.push(NodeCreateRequest {
+            node_id: node_id.clone(),
+            content: content.to_string(),
+            parent_id,
+            before_sibling_id,
+            node_type,
+            metadata,
+        });
+        branch.push(node_id);
+    }
+    requests
+}
+
+fn parse_line(line: &str) -> OutlineLine<'_> {
+    let text = line.trim_start();
+    let indent = line[..line.len() - text.len()]
+        .chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum();
+    let text = text.trim_end();
+
+    match text.strip_prefix(['-', '*', '+']) {
+        Some(rest) if rest.is_empty() || rest.starts_with(' ') => OutlineLine {
+            indent,
+            text: rest.trim_start(),
+            bulleted: true,
+        },
+        _ => OutlineLine {
+            indent,
+            text,
+            bulleted: false,
+        },
+    }
+}
+
+/// A leading `[ ]` or `[x]`, as the completed flag and the remaining text
+fn checkbox(text: &str) -> Option<(bool, &str)> {
+    let (completed, rest) = [("[ ]", false), ("[x]", true), ("[X]", true)]
+        .into_iter()
+        .find_map(|(prefix, completed)| text.strip_prefix(prefix).map(|rest| (completed, rest)))?;
+    (rest.is_empty() || rest.starts_with(' ')).then(|| (completed, rest.trim_start()))
+}
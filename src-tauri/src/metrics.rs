@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Number of recent query latencies kept for percentile reporting
+pub const LATENCY_WINDOW: usize = 500;
+
+/// Latency percentiles, in milliseconds, over the most recent queries. The percentiles
+/// are `None` until at least one query has been recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub sample_count: usize,
+    pub p50_ms: Option<u64>,
+    pub p90_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+}
+
+/// Ring buffer of recent query latencies; the oldest sample is dropped once full
+#[derive(Debug, Clone)]
+pub struct LatencyTracker {
+    samples: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::with_capacity(LATENCY_WINDOW)
+    }
+}
+
+impl LatencyTracker {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples
+            .push_back(u64::try_from(latency.as_millis()).unwrap_or(u64::MAX));
+    }
+
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        let mut sorted: Vec<u64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+
+        LatencyPercentiles {
+            sample_count: sorted.len(),
+            p50_ms: nearest_rank(&sorted, 50),
+            p90_ms: nearest_rank(&sorted, 90),
+            p99_ms: nearest_rank(&sorted, 99),
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending sample: the smallest value with at least
+/// `percentile`% of the sample at or below it.
+fn nearest_rank(sorted: &[u64], percentile: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percentile * sorted.len()).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+use crate::error::{AppError, AppResult};
+
+/// Set once an operation should stop; shared between the operation and whoever cancels it
+#[derive(Debug, Default)]
+pub struct CancellationToken {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` has been called
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking the flag so a cancel in between isn't missed
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// In-flight operations by the ID the frontend gave them
+#[derive(Debug, Default)]
+pub struct OperationRegistry {
+    operations: Mutex<HashMap<String, Arc<CancellationToken>>>,
+}
+
+impl OperationRegistry {
+    /// Run `operation` under `operation_id` until it finishes or is cancelled, in which
+    /// case it is dropped at its next await point and `AppError::Cancelled` is returned.
+    /// The ID is released when this returns or is itself dropped.
+    pub async fn run<T, E, F>(&self, operation_id: &str, operation: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<AppError>,
+    {
+        let token = self.register(operation_id)?;
+        let _registration = Registration {
+            registry: self,
+            operation_id,
+        };
+
+        tokio::select! {
+            _ = token.cancelled() => Err(AppError::Cancelled(operation_id.to_string()).into()),
+            result = operation => result,
+        }
+    }
+
+    /// Trip the token of an in-flight operation. Returns false when no operation with
+    /// that ID is running, for example because it has already finished.
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.lock().get(operation_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_running(&self, operation_id: &str) -> bool {
+        self.lock().contains_key(operation_id)
+    }
+
+    fn register(&self, operation_id: &str) -> AppResult<Arc<CancellationToken>> {
+        if operation_id.trim().is_empty() {
+            return Err(AppError::InvalidInput(
+                "Operation ID cannot be empty".to_string(),
+            ));
+        }
+
+        let mut operations = self.lock();
+        if operations.contains_key(operation_id) {
+            return Err(AppError::InvalidInput(format!(
+                "Operation {} is already running",
+                operation_id
+            )));
+        }
+        let token = Arc::new(CancellationToken::default());
+        operations.insert(operation_id.to_string(), token.clone());
+        Ok(token)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<CancellationToken>>> {
+        // The map is only touched in short critical sections that can't panic midway
+        self.operations.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Removes an operation from the registry when it ends, however it ends
+struct Registration<'a> {
+    registry: &'a OperationRegistry,
+    operation_id: &'a str,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.registry.lock().remove(self.operation_id);
+    }
+}
@@ -0,0 +1,237 @@
+use nodespace_core_types::Node;
+
+use crate::hierarchy::OutlineNode;
+
+const PRINT_STYLES: &str =
+    "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;\
+line-height:1.5;color:#1a1a1a;max-width:48rem;margin:2rem auto;padding:0 1rem;}\
+h1{font-size:1.5rem;border-bottom:1px solid #ddd;padding-bottom:.5rem;}\
+ul{padding-left:1.25rem;}li{margin:.25rem 0;}\
+img{max-width:100%;height:auto;display:block;margin:.5rem 0;}\
+.task input{margin-right:.5rem;}.completed{text-decoration:line-through;color:#777;}\
+@media print{body{margin:0;max-width:none;}li{break-inside:avoid;}}";
+
+/// Render an outline as a self-contained HTML document suitable for printing. Image
+/// files are inlined through `inline_image`, which decides what may be read from disk.
+pub fn render_html_document(
+    title: &str,
+    outline: &[OutlineNode],
+    inline_image: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    let mut body = String::new();
+    render_html_list(outline, inline_image, &mut body);
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{styles}</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = escape_html(title),
+        styles = PRINT_STYLES,
+        body = body
+    )
+}
+
+fn render_html_list(
+    items: &[OutlineNode],
+    inline_image: &dyn Fn(&str) -> Option<String>,
+    out: &mut String,
+) {
+    if items.is_empty() {
+        return;
+    }
+
+    out.push_str("<ul>\n");
+    for item in items {
+        let node = &item.node;
+        let text = escape_html(node.content.as_str().unwrap_or_default()).replace('\n', "<br>");
+
+        if is_image_node(node) {
+            let alt = escape_html(image_filename(node).unwrap_or("image"));
+            match image_source(node, inline_image) {
+                Some(src) => out.push_str(&format!(
+                    "<li class=\"image\"><img src=\"{}\" alt=\"{}\">",
+                    escape_html(&src),
+                    alt
+                )),
+                None => out.push_str(&format!("<li class=\"image\">[{}]", alt)),
+            }
+        } else if node.r#type == "task" {
+            let completed = is_task_completed(node);
+            out.push_str(&format!(
+                "<li class=\"task{}\"><input type=\"checkbox\" disabled{}>{}",
+                if completed { " completed" } else { "" },
+                if completed { " checked" } else { "" },
+                text
+            ));
+        } else {
+            out.push_str(&format!("<li>{}", text));
+        }
+
+        render_html_list(&item.children, inline_image, out);
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n");
+}
+
+/// Render an outline as nested Markdown bullets, two spaces of indentation per level.
+/// Tasks become `- [ ]`/`- [x]` checkboxes and images become `![filename](path)`.
+pub fn render_markdown_outline(outline: &[OutlineNode]) -> String {
+    let mut out = String::new();
+    render_markdown_items(outline, 0, &mut out);
+    out
+}
+
+fn render_markdown_items(items: &[OutlineNode], depth: usize, out: &mut String) {
+    for item in items {
+        let node = &item.node;
+        let indent = "  ".repeat(depth);
+
+        let line = if is_image_node(node) {
+            let path = node
+                .metadata
+                .as_ref()
+                .and_then(|m| m.get("file_path"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            format!("![{}]({})", image_filename(node).unwrap_or("image"), path)
+        } else {
+            let text = node
+                .content
+                .as_str()
+                .unwrap_or_default()
+                .trim_end()
+                .replace('\n', &format!("\n{}  ", indent));
+            if node.r#type == "task" {
+                let mark = if is_task_completed(node) { "x" } else { " " };
+                format!("[{}] {}", mark, text)
+            } else {
+                text
+            }
+        };
+
+        out.push_str(&format!("{}- {}\n", indent, line));
+        render_markdown_items(&item.children, depth + 1, out);
+    }
+}
+
+/// Render a date's AI chat nodes as a single Markdown transcript, in the order given.
+/// A date without conversations still yields a valid document with a placeholder line.
+pub fn render_conversation_transcript(date: &str, chats: &[Node]) -> String {
+    let mut out = format!("# AI Conversations for {}\n\n", date);
+    if chats.is_empty() {
+        out.push_str("_No AI conversations on this date._\n");
+        return out;
+    }
+
+    for (index, chat) in chats.iter().enumerate() {
+        let metadata = chat.metadata.as_ref();
+        let field = |key: &str| {
+            metadata
+                .and_then(|m| m.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let question = field("question")
+            .or_else(|| chat.content.as_str().map(str::trim))
+            .unwrap_or_default();
+        out.push_str(&format!("## {}. {}\n\n", index + 1, question));
+        out.push_str(field("response").unwrap_or("_No response recorded._"));
+        out.push_str("\n\n");
+
+        if let Some(confidence) = metadata
+            .and_then(|m| m.get("overall_confidence"))
+            .and_then(|v| v.as_f64())
+        {
+            out.push_str(&format!("Confidence: {:.0}%\n\n", confidence * 100.0));
+        }
+
+        let sources: Vec<String> = metadata
+            .and_then(|m| m.get("node_sources"))
+            .and_then(|v| v.as_array())
+            .map(|sources| {
+                sources
+                    .iter()
+                    .filter_map(|s| {
+                        s.as_str()
+                            .or_else(|| s.get("node_id").and_then(|id| id.as_str()))
+                            .map(|s| s.to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !sources.is_empty() {
+            out.push_str(&format!("Sources: {}\n\n", sources.join(", ")));
+        }
+    }
+    out
+}
+
+/// Escape text for safe inclusion in HTML element content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn is_image_node(node: &Node) -> bool {
+    node.r#type == "image"
+        || node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("node_type"))
+            .and_then(|v| v.as_str())
+            == Some("image")
+}
+
+/// AI chat nodes are stored with their question and answer in metadata
+pub fn is_ai_chat_node(node: &Node) -> bool {
+    let Some(metadata) = node.metadata.as_ref() else {
+        return false;
+    };
+    metadata.get("node_type").and_then(|v| v.as_str()) == Some(crate::AI_CHAT_NODE_TYPE)
+        || (metadata.get("question").is_some() && metadata.get("response").is_some())
+}
+
+pub fn is_task_completed(node: &Node) -> bool {
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get("completed"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn image_filename(node: &Node) -> Option<&str> {
+    node.metadata
+        .as_ref()
+        .and_then(|m| m.get("filename"))
+        .and_then(|v| v.as_str())
+}
+
+/// Inline the stored image as a data URI so the document survives being saved or printed
+/// without access to the vault; falls back to a stored data URI, and otherwise to the
+/// placeholder.
+fn image_source(node: &Node, inline_image: &dyn Fn(&str) -> Option<String>) -> Option<String> {
+    let metadata = node.metadata.as_ref()?;
+    if let Some(src) = metadata
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .and_then(inline_image)
+    {
+        return Some(src);
+    }
+
+    metadata
+        .get("blob_url")
+        .and_then(|v| v.as_str())
+        .filter(|s| s.starts_with("data:image/"))
+        .map(|s| s.to_string())
+}
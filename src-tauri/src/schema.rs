@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use nodespace_core_types::{Node, NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// Name of the marker file recording the schema version inside the database directory
+pub const VERSION_MARKER_FILE: &str = "nodespace_schema_version";
+
+/// Schema version written by this build
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Reported for databases created before version markers existed
+pub const LEGACY_SCHEMA_VERSION: &str = "legacy";
+
+/// Read the version marker from `db_dir`, returning `None` for a pre-versioning database
+pub fn read_schema_version(db_dir: &Path) -> AppResult<Option<u32>> {
+    let marker = db_dir.join(VERSION_MARKER_FILE);
+    match std::fs::read_to_string(&marker) {
+        Ok(contents) => contents.trim().parse::<u32>().map(Some).map_err(|e| {
+            AppError::DataStore(format!(
+                "Corrupt schema version marker at {}: {}",
+                marker.display(),
+                e
+            ))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::DataStore(format!(
+            "Failed to read schema version marker: {}",
+            e
+        ))),
+    }
+}
+
+/// Human-readable version string, using the legacy sentinel when no marker exists
+pub fn describe_schema_version(version: Option<u32>) -> String {
+    version
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| LEGACY_SCHEMA_VERSION.to_string())
+}
+
+pub fn write_schema_version(db_dir: &Path, version: u32) -> AppResult<()> {
+    std::fs::write(db_dir.join(VERSION_MARKER_FILE), format!("{}\n", version))
+        .map_err(|e| AppError::DataStore(format!("Failed to write schema version marker: {}", e)))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationSummary {
+    pub from_version: String,
+    pub to_version: String,
+    pub already_current: bool,
+    pub root_ids_backfilled: usize,
+    pub metadata_normalized: usize,
+}
+
+/// Node updates required to bring a database forward to `CURRENT_SCHEMA_VERSION`
+#[derive(Debug, Default)]
+pub struct MigrationPlan {
+    /// v1: nodes missing `root_id`, paired with the date node at the top of their chain
+    pub root_id_backfills: Vec<(NodeId, NodeId)>,
+    /// v2: nodes whose metadata used legacy camelCase keys, with the rewritten metadata
+    pub metadata_rewrites: Vec<(NodeId, serde_json::Value)>,
+}
+
+impl MigrationPlan {
+    pub fn is_empty(&self) -> bool {
+        self.root_id_backfills.is_empty() && self.metadata_rewrites.is_empty()
+    }
+}
+
+/// Work out which forward migrations apply to `nodes` at `from_version`. Each step only
+/// selects nodes that still need it, so planning again after applying is a no-op.
+pub fn plan_migration(nodes: &[Node], from_version: Option<u32>) -> MigrationPlan {
+    let from = from_version.unwrap_or(0);
+    let mut plan = MigrationPlan::default();
+
+    if from < 1 {
+        let by_id: HashMap<&str, &Node> = nodes.iter().map(|n| (n.id.0.as_str(), n)).collect();
+        for node in nodes
+            .iter()
+            .filter(|n| n.root_id.is_none() && n.r#type != "date")
+        {
+            if let Some(root) = top_ancestor(node, &by_id).filter(|r| r.r#type == "date") {
+                plan.root_id_backfills
+                    .push((node.id.clone(), root.id.clone()));
+            }
+        }
+    }
+
+    if from < 2 {
+        for node in nodes {
+            if let Some(rewritten) = node.metadata.as_ref().and_then(normalize_metadata_keys) {
+                plan.metadata_rewrites.push((node.id.clone(), rewritten));
+            }
+        }
+    }
+
+    plan
+}
+
+fn top_ancestor<'a>(node: &'a Node, by_id: &HashMap<&str, &'a Node>) -> Option<&'a Node> {
+    let mut current = node;
+    for _ in 0..by_id.len() {
+        match current
+            .parent_id
+            .as_ref()
+            .and_then(|p| by_id.get(p.0.as_str()))
+        {
+            Some(parent) => current = *parent,
+            None => return (current.id.0 != node.id.0).then_some(current),
+        }
+    }
+    // Parent chain loops; there is no well-defined root
+    None
+}
+
+/// Rewrite legacy camelCase top-level metadata keys to snake_case. Returns `None` when
+/// nothing needs to change; an existing snake_case key always wins over its legacy twin.
+pub fn normalize_metadata_keys(metadata: &serde_json::Value) -> Option<serde_json::Value> {
+    let object = metadata.as_object()?;
+    if !object
+        .keys()
+        .any(|k| k.chars().any(|c| c.is_ascii_uppercase()))
+    {
+        return None;
+    }
+
+    let mut normalized = serde_json::Map::new();
+    for (key, value) in object {
+        if !key.chars().any(|c| c.is_ascii_uppercase()) {
+            normalized.insert(key.clone(), value.clone());
+        }
+    }
+    for (key, value) in object {
+        if key.chars().any(|c| c.is_ascii_uppercase()) {
+            normalized
+                .entry(to_snake_case(key))
+                .or_insert_with(|| value.clone());
+        }
+    }
+    Some(serde_json::Value::Object(normalized))
+}
+
+fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for (index, c) in key.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
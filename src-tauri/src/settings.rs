@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+
+/// User preferences persisted across restarts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceSettings {
+    pub default_model: Option<String>,
+    /// Outline expand/collapse state by node ID. It is view state, so it lives here rather
+    /// than on the nodes, where writing it would bump their `updated_at`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub collapsed_nodes: BTreeMap<String, bool>,
+}
+
+impl WorkspaceSettings {
+    /// Location of the settings file, next to the `logs` directory
+    pub fn default_path() -> AppResult<PathBuf> {
+        std::env::current_dir()
+            .map(|dir| dir.join("workspace_settings.json"))
+            .map_err(|e| AppError::Internal(format!("Failed to resolve settings path: {}", e)))
+    }
+
+    /// Load settings from `path`, falling back to defaults when the file doesn't exist
+    pub fn load_from(path: &Path) -> AppResult<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(AppError::Internal(format!(
+                "Failed to read settings from {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> AppResult<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents).map_err(|e| {
+            AppError::Internal(format!(
+                "Failed to write settings to {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Load the persisted settings, logging and falling back to defaults on any error
+    pub fn load() -> Self {
+        match Self::default_path().and_then(|path| Self::load_from(&path)) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::warn!("Using default workspace settings: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        self.save_to(&Self::default_path()?)
+    }
+}
@@ -5,6 +5,7 @@ import { NodeSpaceCallbacks } from 'nodespace-core-ui';
 import { countAllNodes } from 'nodespace-core-ui';
 import DatePicker from 'react-datepicker';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage as describeError, isCommandError, isRetryableError } from './utils/errors';
 import "react-datepicker/dist/react-datepicker.css";
 import "nodespace-core-ui/dist/nodeSpace.css";
 import './App.css';
@@ -36,8 +37,10 @@ function App() {
       try {
         await invoke('update_node_content', { nodeId, content });
       } catch (error) {
-        const errorMessage = error instanceof Error ? error.message : String(error);
-        if (!errorMessage.includes('Record not found') && !errorMessage.includes('not found')) {
+        const errorMessage = describeError(error);
+        const notFound = (isCommandError(error) && error.type === 'NotFound')
+          || errorMessage.includes('Record not found') || errorMessage.includes('not found');
+        if (!notFound) {
           console.error('Failed to auto-save node content:', error);
         }
       }
@@ -71,7 +74,8 @@ function App() {
         generation_time_ms?: number;
         overall_confidence?: number;
       }>('process_query', {
-        question: request.query.trim()
+        question: request.query.trim(),
+        operationId: crypto.randomUUID()
       });
 
       const endTime = Date.now();
@@ -103,12 +107,17 @@ function App() {
         metadata: metadata
       };
     } catch (error) {
+      // The backend already retried while the models loaded; tell the user to wait rather
+      // than reporting a failure
+      const stillLoading = isRetryableError(error);
       return {
-        answer: "I apologize, but I encountered an error while processing your question. Please try again.",
+        answer: stillLoading
+          ? "The AI models are still loading. Please try your question again in a moment."
+          : "I apologize, but I encountered an error while processing your question. Please try again.",
         metadata: {
           question: request.query,
           response: "Error occurred during processing",
-          error: error instanceof Error ? error.message : String(error),
+          error: describeError(error),
           confidence: 0.0,
           overall_confidence: 0.0,
           node_sources: [],
@@ -193,16 +202,20 @@ function App() {
       }
 
       try {
-        const searchResults = await invoke<Array<{
-          node: BaseNode;
-          score: number;
-          snippet: string;
-        }>>('semantic_search', {
+        const searchPage = await invoke<{
+          results: Array<{
+            node: BaseNode;
+            score: number;
+            snippet: string;
+          }>;
+          offset: number;
+          total_available: number;
+        }>('semantic_search', {
           query: searchQuery.trim(),
           limit: 10
         });
 
-        return searchResults.map(result => ({
+        return searchPage.results.map(result => ({
           ...result.node,
           searchScore: result.score,
           searchSnippet: result.snippet
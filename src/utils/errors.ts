@@ -0,0 +1,25 @@
+/** Error returned by a Tauri command: the backend's `AppError`, tagged with its variant */
+export interface CommandError {
+  type: string
+  message: string
+}
+
+export function isCommandError(error: unknown): error is CommandError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as CommandError).type === 'string' &&
+    typeof (error as CommandError).message === 'string'
+  )
+}
+
+/** Human-readable text for anything a command or the frontend itself may throw */
+export function errorMessage(error: unknown): string {
+  if (isCommandError(error)) return error.message
+  return error instanceof Error ? error.message : String(error)
+}
+
+/** Errors that clear on their own, such as models still loading, and are worth retrying */
+export function isRetryableError(error: unknown): boolean {
+  return isCommandError(error) && error.type === 'ServiceInitializing'
+}